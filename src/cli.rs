@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(name = "cpam")]
@@ -20,6 +20,14 @@ pub enum Commands {
     Build(BuildArgs),
     /// プロジェクトを実行する
     Run(RunArgs),
+    /// 依存関係を木構造で表示する
+    Tree(TreeArgs),
+    /// cpam.lock の依存ライブラリを要求の範囲内で最新にする
+    Update(UpdateArgs),
+    /// 依存ライブラリのロック中・互換・最新のバージョンを比較する
+    Outdated(OutdatedArgs),
+    /// 依存ライブラリのソースを vendor/ にコピーする
+    Vendor(VendorArgs),
 }
 
 #[derive(Parser)]
@@ -27,11 +35,17 @@ pub struct NewArgs {
     /// プロジェクト種別: "bin" (バイナリ)または "lib" (ライブラリ)（デフォルトは "bin"）
     #[arg(long, default_value = "bin")]
     pub project_type: String,
+    /// ライブラリの種類: static, shared, interface（--project-type lib の場合、省略すると対話形式で選択します）
+    #[arg(long)]
+    pub library_type: Option<String>,
     /// プロジェクト名（省略すると対話形式で入力します）
     pub name: Option<String>,
     /// 使用する言語: c, cpp, cuda（省略すると対話形式で選択します）
     #[arg(long)]
     pub language: Option<String>,
+    /// 言語の規格: c11, c17, c++17, c++20, gnu++17 など（省略すると対話形式で選択します）
+    #[arg(long)]
+    pub standard: Option<String>,
     /// 使用するビルドツール: make, ninja（省略すると対話形式で選択します）
     #[arg(long)]
     pub build_tool: Option<String>,
@@ -47,9 +61,48 @@ pub struct AddArgs {
     /// バージョン（任意）
     #[arg(long)]
     pub version: Option<String>,
-    /// 取得元（Git URLなど、任意）
+    /// 取得元（Git URL、ローカルパス、アーカイブURL、任意）
     #[arg(long)]
     pub source: Option<String>,
+    /// Gitのタグ（--source が Git リポジトリの場合）
+    #[arg(long, conflicts_with_all = ["branch", "rev"])]
+    pub tag: Option<String>,
+    /// Gitのブランチ（--source が Git リポジトリの場合）
+    #[arg(long, conflicts_with = "rev")]
+    pub branch: Option<String>,
+    /// Gitのコミット（--source が Git リポジトリの場合）
+    #[arg(long)]
+    pub rev: Option<String>,
+    /// アーカイブのSHA-256（--source がアーカイブURLの場合。省略するとダウンロードして計算した値を記録します）
+    #[arg(long)]
+    pub sha256: Option<String>,
+    /// 検索するレジストリ名（--source を省略した場合、省略時は全レジストリを検索）
+    #[arg(long, conflicts_with = "source")]
+    pub registry: Option<String>,
+    /// システムにインストール済みのライブラリを find_package で探す
+    #[arg(long, conflicts_with_all = ["source", "registry"])]
+    pub system: bool,
+    /// find_package に渡すパッケージ名（省略時は依存ライブラリ名、--system を含意）
+    #[arg(long, conflicts_with_all = ["source", "registry", "pkg_config"])]
+    pub find_package: Option<String>,
+    /// システムのライブラリを pkg-config のモジュール名で探す
+    #[arg(long, conflicts_with_all = ["source", "registry", "system"])]
+    pub pkg_config: Option<String>,
+    /// リンクするCMakeターゲット（複数指定可）
+    #[arg(long = "target", value_name = "TARGET")]
+    pub targets: Vec<String>,
+    /// 依存ライブラリを有効にするプラットフォーム（"linux", "windows+msvc" など、複数指定可）
+    #[arg(long = "platform", value_name = "PLATFORM")]
+    pub platforms: Vec<String>,
+    /// [features] で有効にした場合のみ使う依存ライブラリとして追加する
+    #[arg(long)]
+    pub optional: bool,
+    /// テストやベンチマーク用として [dev-dependencies] に追加する
+    #[arg(long, conflicts_with = "build")]
+    pub dev: bool,
+    /// ビルド時のツールとして [build-dependencies] に追加する
+    #[arg(long)]
+    pub build: bool,
 }
 
 #[derive(Parser)]
@@ -69,6 +122,20 @@ pub struct BuildArgs {
     /// CMakeジェネレーター（省略時はcpam.tomlから読み取り）
     #[arg(long)]
     pub generator: Option<String>,
+    /// cpam.lock を更新せず、内容が古い場合は失敗する
+    #[arg(long)]
+    pub locked: bool,
+    /// ネットワークを使わず、共有キャッシュのみからビルドする
+    #[arg(long)]
+    pub offline: bool,
+    /// ビルドするワークスペースのメンバー（省略すると全てのメンバー）
+    #[arg(long, short = 'p', value_name = "MEMBER")]
+    pub package: Option<String>,
+    #[command(flatten)]
+    pub features: FeatureArgs,
+    // cpam run が実行する実行ファイルのターゲット（指定するとそれだけをビルドする）
+    #[arg(skip)]
+    pub target: Option<String>,
 }
 
 #[derive(Parser)]
@@ -76,4 +143,70 @@ pub struct RunArgs {
     /// リリースモードで実行する
     #[arg(long)]
     pub release: bool,
+    /// 実行する実行ファイル（プロジェクト本体、[[bin]] または src/bin/ の名前）
+    #[arg(long, conflicts_with = "example")]
+    pub bin: Option<String>,
+    /// 実行する example（[[example]] または examples/ の名前）
+    #[arg(long)]
+    pub example: Option<String>,
+    /// cpam.lock を更新せず、内容が古い場合は失敗する
+    #[arg(long)]
+    pub locked: bool,
+    /// ネットワークを使わず、共有キャッシュのみからビルドする
+    #[arg(long)]
+    pub offline: bool,
+    /// 実行するワークスペースのメンバー（メンバーのディレクトリでは省略できます）
+    #[arg(long, short = 'p', value_name = "MEMBER")]
+    pub package: Option<String>,
+    #[command(flatten)]
+    pub features: FeatureArgs,
+}
+
+#[derive(Args, Clone, Default)]
+pub struct FeatureArgs {
+    /// 有効にする feature（カンマ区切り）
+    #[arg(long, short = 'F', value_delimiter = ',')]
+    pub features: Vec<String>,
+    /// [features] の全ての feature を有効にする
+    #[arg(long)]
+    pub all_features: bool,
+    /// default feature を有効にしない
+    #[arg(long)]
+    pub no_default_features: bool,
+}
+
+#[derive(Parser)]
+pub struct TreeArgs {
+    /// 表示する深さの上限
+    #[arg(long)]
+    pub depth: Option<usize>,
+    /// 指定した依存ライブラリを要求しているパッケージを逆向きに表示する
+    #[arg(long, short = 'i', value_name = "PKG")]
+    pub invert: Option<String>,
+    /// 複数のパッケージから要求されている依存ライブラリのみ表示する
+    #[arg(long, short = 'd', conflicts_with = "invert")]
+    pub duplicates: bool,
+}
+
+#[derive(Parser)]
+pub struct UpdateArgs {
+    /// 更新する依存ライブラリ名（省略すると全て更新）
+    pub name: Option<String>,
+    /// cpam.lock を書き換えずに変更内容のみ表示する
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser)]
+pub struct OutdatedArgs {
+    /// 表の代わりにJSON形式で出力する
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct VendorArgs {
+    /// cpam.lock を更新せず、内容が古い場合は失敗する
+    #[arg(long)]
+    pub locked: bool,
 }
@@ -0,0 +1,541 @@
+use crate::conan;
+use crate::features;
+use crate::config::{Backend, CpamConfig, Dependency, DependencyKind};
+use crate::lockfile::{LockedPackage, Lockfile, LOCKFILE_NAME};
+use crate::platform;
+use crate::vcpkg;
+use crate::version;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+// ビルドディレクトリに生成する依存関係用CMakeファイル名
+pub const DEPS_FILE_NAME: &str = "cpam_deps.cmake";
+
+// CMakeLists.txt から生成ファイルを取り込むための行
+// ワークスペースではメンバーごとのビルドディレクトリに生成するので、CMAKE_CURRENT_BINARY_DIR から探す
+pub const DEPS_INCLUDE_LINE: &str = "include(${CMAKE_CURRENT_BINARY_DIR}/cpam_deps.cmake OPTIONAL)";
+
+// dependencies と feature の定義をまとめてリンクするためのターゲット
+pub const DEPENDENCIES_TARGET: &str = "cpam_dependencies";
+
+// dev-dependencies をまとめてリンクするためのターゲット
+pub const DEV_DEPENDENCIES_TARGET: &str = "cpam_dev_dependencies";
+
+// 依存ライブラリのターゲットへその依存ライブラリをリンクする関数
+// INTERFACE ライブラリには INTERFACE で、ALIAS は元のターゲットへリンクする
+const LINK_FUNCTION: &str = "
+function(cpam_link_dependencies target)
+    get_target_property(aliased ${target} ALIASED_TARGET)
+    if(aliased)
+        set(target ${aliased})
+    endif()
+    get_target_property(type ${target} TYPE)
+    if(type STREQUAL \"INTERFACE_LIBRARY\")
+        target_link_libraries(${target} INTERFACE ${ARGN})
+    else()
+        target_link_libraries(${target} PUBLIC ${ARGN})
+    endif()
+endfunction()
+";
+
+// 生成ファイルの先頭の注記
+const GENERATED_NOTE: &str = "# このファイルは cpam build によって自動生成されます。手動で編集しないでください。\n";
+
+// ロックファイルの内容から FetchContent の宣言とリンク設定を生成する
+// sources にはキャッシュや vendor に用意したソースディレクトリを渡し、FetchContent のダウンロードを省略させる
+// config からは無効な feature の optional な依存ライブラリを外しておく
+pub fn render_dependencies(
+    config: &CpamConfig,
+    lockfile: &Lockfile,
+    sources: &BTreeMap<String, PathBuf>,
+    enabled_features: &BTreeSet<String>,
+) -> io::Result<String> {
+    let mut content = String::from(GENERATED_NOTE);
+
+    if lockfile.packages.is_empty() && config.backend_dependencies()?.is_empty() && config.features.is_none() {
+        return Ok(content);
+    }
+
+    // 依存ライブラリ側の CMakeLists.txt から再度取り込まれても二重に処理しない
+    content.push_str("include_guard(GLOBAL)\ninclude(FetchContent)\n");
+
+    // 依存ライブラリの option() が cmake_options で設定した通常の変数を優先するようにする
+    content.push_str("set(CMAKE_POLICY_DEFAULT_CMP0077 NEW)\n");
+
+    content.push_str(&render_feature_options(config, enabled_features));
+    content.push_str(&render_packages(config, lockfile, sources)?);
+    content.push_str(&render_links(config, lockfile, enabled_features)?);
+    Ok(content)
+}
+
+// ワークスペースのルートで取り込む、全メンバーが共有する依存ライブラリ
+// config には Workspace::combined でまとめた設定を渡す
+pub fn render_workspace_dependencies(config: &CpamConfig, lockfile: &Lockfile, sources: &BTreeMap<String, PathBuf>) -> io::Result<String> {
+    let mut content = String::from(GENERATED_NOTE);
+    if lockfile.packages.is_empty() {
+        return Ok(content);
+    }
+
+    content.push_str("include_guard(GLOBAL)\ninclude(FetchContent)\n");
+    content.push_str("set(CMAKE_POLICY_DEFAULT_CMP0077 NEW)\n");
+    content.push_str(&render_packages(config, lockfile, sources)?);
+    Ok(content)
+}
+
+// ワークスペースのメンバーのリンク設定（依存ライブラリはルートで取り込み済み）
+pub fn render_member_dependencies(config: &CpamConfig, lockfile: &Lockfile, enabled_features: &BTreeSet<String>) -> io::Result<String> {
+    let mut content = String::from(GENERATED_NOTE);
+    if config.all_dependencies()?.is_empty() && config.features.is_none() {
+        return Ok(content);
+    }
+
+    content.push_str("include_guard(GLOBAL)\n");
+    content.push_str(&render_feature_options(config, enabled_features));
+    content.push_str(&render_links(config, lockfile, enabled_features)?);
+    Ok(content)
+}
+
+// feature ごとの ON/OFF を CMake のオプションとして公開する
+fn render_feature_options(config: &CpamConfig, enabled_features: &BTreeSet<String>) -> String {
+    let mut content = String::new();
+    if let Some(feature_table) = &config.features {
+        content.push('\n');
+        for feature in feature_table.keys().filter(|feature| feature.as_str() != features::DEFAULT_FEATURE) {
+            content.push_str(&format!(
+                "set({} {} CACHE BOOL \"cpam feature '{}'\" FORCE)\n",
+                features::cmake_variable(config, feature),
+                if enabled_features.contains(feature) { "ON" } else { "OFF" },
+                feature
+            ));
+        }
+    }
+    content
+}
+
+// 依存ライブラリの宣言と取り込み、依存ライブラリ同士のリンク、バックエンドのパッケージの検索
+fn render_packages(config: &CpamConfig, lockfile: &Lockfile, sources: &BTreeMap<String, PathBuf>) -> io::Result<String> {
+    let mut content = String::new();
+
+    // 無効な optional の依存ライブラリからしか使われないパッケージは取り込まない
+    let language = config.cmake_language();
+    let platforms = package_platforms(config, lockfile)?;
+    let packages: Vec<&LockedPackage> = lockfile.packages.iter().filter(|package| platforms.contains_key(&package.name)).collect();
+
+    // 先に全て宣言しておくと、依存ライブラリ内の同名の宣言よりこちらが優先される
+    for package in &packages {
+        let name = &package.name;
+        if let Some(source_dir) = sources.get(name) {
+            content.push_str(&format!(
+                "\nset(FETCHCONTENT_SOURCE_DIR_{} \"{}\")",
+                name.to_uppercase(),
+                source_dir.to_string_lossy().replace('\\', "/")
+            ));
+        }
+
+        if let Some(git) = &package.git {
+            content.push_str(&format!("\nFetchContent_Declare(\n    {}\n    GIT_REPOSITORY {}\n", name, git));
+            if let Some(commit) = &package.commit {
+                content.push_str(&format!("    GIT_TAG {}\n", commit));
+            }
+            content.push_str(")\n");
+        } else if let Some(url) = &package.url {
+            content.push_str(&format!("\nFetchContent_Declare(\n    {}\n    URL {}\n", name, url));
+            if let Some(checksum) = &package.checksum {
+                content.push_str(&format!("    URL_HASH SHA256={}\n", checksum));
+            }
+            content.push_str(")\n");
+        } else if package.path.is_none() && package.find_package.is_none() && package.pkg_config.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} の '{}' に取得先が記録されていません。", LOCKFILE_NAME, name),
+            ));
+        }
+    }
+
+    // 依存される側から順に取り込む（platforms の指定があるものは条件に一致する場合のみ）
+    content.push('\n');
+    let condition = |name: &str| {
+        platforms.get(name).cloned().flatten().map(|platforms| platform::cmake_condition(&platforms, language))
+    };
+    // pkg-config は条件の無いパッケージで使う場合のみ先に探し、条件付きのものは if() の中で探す
+    if packages.iter().any(|package| package.pkg_config.is_some() && condition(&package.name).is_none()) {
+        content.push_str("find_package(PkgConfig REQUIRED)\n");
+    }
+    for package in &packages {
+        let minimum = version::minimum_version(&version::parse_requirement(&package.requirement)?);
+        let line = if let Some(find_package) = &package.find_package {
+            let version = minimum.map(|minimum| format!(" {}", minimum)).unwrap_or_default();
+            format!("find_package({}{} REQUIRED)", find_package, version)
+        } else if let Some(module) = &package.pkg_config {
+            let version = minimum.map(|minimum| format!(">={}", minimum)).unwrap_or_default();
+            let line = format!("pkg_check_modules({} REQUIRED IMPORTED_TARGET {}{})", pkg_config_prefix(&package.name), module, version);
+            match condition(&package.name) {
+                Some(_) => format!("find_package(PkgConfig REQUIRED)\n{}", line),
+                None => line,
+            }
+        } else {
+            match &package.path {
+                // ローカルパスの依存はそのままサブディレクトリとして取り込む
+                Some(path) => {
+                    let source_dir = match sources.get(&package.name) {
+                        Some(source_dir) => source_dir.to_string_lossy().replace('\\', "/"),
+                        None => resolve_local_path(&package.name, path)?,
+                    };
+                    format!("add_subdirectory(\"{}\" \"${{CMAKE_BINARY_DIR}}/_deps/{}-build\")", source_dir, package.name)
+                }
+                None => format!("FetchContent_MakeAvailable({})", package.name),
+            }
+        };
+        let options = package_options(config, &package.name)?;
+        content.push_str(&conditional(condition(&package.name).as_deref(), &with_options(&options, &line)));
+    }
+
+    // cpam で管理された依存ライブラリを、その依存ライブラリへリンク
+    // （レジストリのパッケージは自身の CMakeLists.txt でリンクする前提）
+    let mut child_links = String::new();
+    for package in &packages {
+        let children = match &package.dependencies {
+            Some(children) if !package.source.starts_with("registry") => children,
+            _ => continue,
+        };
+
+        let child_targets: Vec<String> = children
+            .iter()
+            .filter_map(|child| lockfile.find(child))
+            .flat_map(package_targets)
+            .collect();
+        for target in package_targets(package) {
+            child_links.push_str(&format!(
+                "\nif(TARGET {})\n    cpam_link_dependencies({} {})\nendif()\n",
+                target, target, child_targets.join(" ")
+            ));
+        }
+    }
+    if !child_links.is_empty() {
+        content.push_str(LINK_FUNCTION);
+        content.push_str(&child_links);
+    }
+
+    // vcpkg や Conan がインストールしたパッケージを探す
+    // （build-dependencies はホスト向けのツールとして入り、CMake の設定ファイルが無いので探さない）
+    let backend_dependencies: Vec<_> = config
+        .backend_dependencies()?
+        .into_iter()
+        .filter(|(kind, _, _)| *kind != DependencyKind::Build)
+        .collect();
+    if !backend_dependencies.is_empty() {
+        if !content.ends_with("\n\n") {
+            content.push('\n');
+        }
+        for (_, name, dependency) in &backend_dependencies {
+            let Some((package, _)) = backend_package(config, name, dependency)? else {
+                continue;
+            };
+            let condition = dependency.platforms().map(|platforms| platform::cmake_condition(platforms, language));
+            let line = with_options(&dependency.cmake_options()?, &format!("find_package({} REQUIRED)", package));
+            content.push_str(&conditional(condition.as_deref(), &line));
+        }
+    }
+
+    Ok(content)
+}
+
+
+// 直接の依存ライブラリと feature の定義をまとめたターゲットを作り、プロジェクトへリンクする
+fn render_links(config: &CpamConfig, lockfile: &Lockfile, enabled_features: &BTreeSet<String>) -> io::Result<String> {
+    let mut content = String::new();
+    let language = config.cmake_language();
+
+    // 直接の依存ライブラリのターゲットを、テーブルの種類に応じてリンクする
+    // （build-dependencies はビルド時のツールなのでリンクしない）
+    // platforms の条件ごとにまとめ、条件の無いものを先にリンクする
+    let mut project_targets: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    let mut dev_targets: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    for (kind, name, dependency) in config.all_dependencies()? {
+        // ワークスペースの他のメンバーはロックファイルに無いので、cpam.toml の targets を使う
+        let targets = match backend_package(config, name, dependency)? {
+            Some((_, targets)) => targets,
+            None => match lockfile.find(name) {
+                Some(package) => package_targets(package),
+                None => dependency.targets().map(<[String]>::to_vec).unwrap_or_default(),
+            },
+        };
+        let condition = dependency.platforms().map(|platforms| platform::cmake_condition(platforms, language));
+        match kind {
+            DependencyKind::Normal => project_targets.entry(condition).or_default().extend(targets),
+            DependencyKind::Dev => dev_targets.entry(condition).or_default().extend(targets),
+            DependencyKind::Build => {}
+        }
+    }
+
+    // [dependencies] と有効な feature の定義を cpam_dependencies にまとめ、プロジェクトと追加の実行ファイルがリンクする
+    let dependencies_target = dependencies_target(config);
+    let dev_dependencies_target = dev_dependencies_target(config);
+    let definitions = features::definitions(config, enabled_features);
+    if !definitions.is_empty() || project_targets.values().any(|targets| !targets.is_empty()) {
+        content.push_str(&format!("\nadd_library({} INTERFACE)\n", dependencies_target));
+        if !definitions.is_empty() {
+            content.push_str(&format!("target_compile_definitions({} INTERFACE {})\n", dependencies_target, definitions.join(" ")));
+        }
+        for (condition, targets) in &project_targets {
+            if !targets.is_empty() {
+                let line = format!("target_link_libraries({} INTERFACE {})", dependencies_target, targets.join(" "));
+                content.push_str(&conditional(condition.as_deref(), &line));
+            }
+        }
+
+        // ライブラリは依存ライブラリと定義を利用側にも伝える（ヘッダーで使われることがあるため）
+        if let Some(project) = &config.project {
+            let scope = match (project.is_library(), project.library_type()?) {
+                (false, _) => "PRIVATE",
+                (true, "interface") => "INTERFACE",
+                (true, _) => "PUBLIC",
+            };
+            content.push_str(&format!("target_link_libraries({} {} {})\n", project.name, scope, dependencies_target));
+        }
+    }
+
+    // テストやベンチマークは cpam_dev_dependencies をリンクして dev-dependencies を使う
+    if dev_targets.values().any(|targets| !targets.is_empty()) {
+        content.push_str(&format!("\nadd_library({} INTERFACE)\n", dev_dependencies_target));
+        for (condition, targets) in &dev_targets {
+            if !targets.is_empty() {
+                let line = format!("target_link_libraries({} INTERFACE {})", dev_dependencies_target, targets.join(" "));
+                content.push_str(&conditional(condition.as_deref(), &line));
+            }
+        }
+    }
+
+    Ok(content)
+}
+
+// バックエンドに任せる依存ライブラリの find_package 名とリンクするターゲット
+fn backend_package(config: &CpamConfig, name: &str, dependency: &Dependency) -> io::Result<Option<(String, Vec<String>)>> {
+    if !config.is_backend_dependency(name, dependency)? {
+        return Ok(None);
+    }
+    Ok(match config.backend()? {
+        Backend::Vcpkg => Some(vcpkg::port_targets(name, dependency)),
+        Backend::Conan => Some(conan::package_targets(name, dependency)),
+        Backend::Cpam => None,
+    })
+}
+
+// cpam_dependencies, cpam_dev_dependencies の実際のターゲット名
+// ワークスペースではメンバーごとに作るので、プロジェクト名を付けて区別する
+pub fn dependencies_target(config: &CpamConfig) -> String {
+    member_target(config, DEPENDENCIES_TARGET)
+}
+
+pub fn dev_dependencies_target(config: &CpamConfig) -> String {
+    member_target(config, DEV_DEPENDENCIES_TARGET)
+}
+
+fn member_target(config: &CpamConfig, target: &str) -> String {
+    match &config.project {
+        Some(project) if config.workspace_member => format!("{}_{}", target, project.name),
+        _ => target.to_string(),
+    }
+}
+
+// cpam.toml（[patch] を含む）で依存ライブラリに指定された cmake_options
+fn package_options<'a>(config: &'a CpamConfig, name: &str) -> io::Result<Vec<(&'a str, String)>> {
+    if let Some(dependency) = config.patch.as_ref().and_then(|patch| patch.get(name)) {
+        return dependency.cmake_options();
+    }
+    match config.all_dependencies()?.into_iter().find(|(_, other, _)| *other == name) {
+        Some((_, _, dependency)) => dependency.cmake_options(),
+        None => Ok(Vec::new()),
+    }
+}
+
+// 依存ライブラリを取り込む間だけ変数を設定する
+// 通常の変数として設定し、取り込み後に元の値へ戻す（元が無ければ unset する）ので、
+// プロジェクト側で先に設定した変数やキャッシュ変数（BUILD_TESTING など）は変わらない
+fn with_options(options: &[(&str, String)], line: &str) -> String {
+    if options.is_empty() {
+        return line.to_string();
+    }
+
+    let mut lines = String::new();
+    for (name, value) in options {
+        lines.push_str(&format!("if(DEFINED {})\n    set(CPAM_SAVED_{} \"${{{}}}\")\nendif()\n", name, name, name));
+        lines.push_str(&format!("set({} \"{}\")\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+    }
+    lines.push_str(line);
+    for (name, _) in options {
+        lines.push_str(&format!(
+            "\nif(DEFINED CPAM_SAVED_{})\n    set({} \"${{CPAM_SAVED_{}}}\")\n    unset(CPAM_SAVED_{})\nelse()\n    unset({})\nendif()",
+            name, name, name, name, name
+        ));
+    }
+    lines
+}
+
+// 条件があれば if() で囲んだ CMake コード
+fn conditional(condition: Option<&str>, lines: &str) -> String {
+    match condition {
+        Some(condition) => {
+            let body: String = lines.lines().map(|line| format!("    {}\n", line)).collect();
+            format!("if({})\n{}endif()\n", condition, body)
+        }
+        None => format!("{}\n", lines),
+    }
+}
+
+// ロックファイルの各パッケージを有効にするプラットフォーム（None は全て）
+// 推移的な依存は、それを使う依存ライブラリの条件を引き継ぐ（条件の無い依存から使われていれば常に有効）
+// cpam.toml の依存ライブラリから辿れないパッケージ（無効な optional の依存ライブラリなど）は含まない
+pub fn package_platforms(config: &CpamConfig, lockfile: &Lockfile) -> io::Result<BTreeMap<String, Option<Vec<String>>>> {
+    let mut platforms: BTreeMap<String, Option<Vec<String>>> = BTreeMap::new();
+    for (_, name, dependency) in config.all_dependencies()? {
+        merge_platforms(&mut platforms, name, dependency.platforms().map(|platforms| platforms.to_vec()));
+    }
+
+    // ロックファイルは依存される側が先に並ぶので、逆順にたどると使う側の条件が先に決まる
+    for package in lockfile.packages.iter().rev() {
+        let Some(inherited) = platforms.get(&package.name).cloned() else {
+            continue;
+        };
+        for child in package.dependencies.iter().flatten() {
+            merge_platforms(&mut platforms, child, inherited.clone());
+        }
+    }
+    Ok(platforms)
+}
+
+fn merge_platforms(platforms: &mut BTreeMap<String, Option<Vec<String>>>, name: &str, added: Option<Vec<String>>) {
+    let merged = match (platforms.remove(name), added) {
+        (None, added) => added,
+        (Some(None), _) | (_, None) => None,
+        (Some(Some(mut existing)), Some(added)) => {
+            for platform in added {
+                if !existing.contains(&platform) {
+                    existing.push(platform);
+                }
+            }
+            Some(existing)
+        }
+    };
+    platforms.insert(name.to_string(), merged);
+}
+
+// パッケージのリンク対象のCMakeターゲット
+// 省略時はパッケージ名（find_package は "<名前>::<名前>"、pkg-config は "PkgConfig::<接頭辞>"）
+fn package_targets(package: &LockedPackage) -> Vec<String> {
+    if let Some(targets) = &package.targets {
+        return targets.clone();
+    }
+    if let Some(find_package) = &package.find_package {
+        return vec![format!("{}::{}", find_package, find_package)];
+    }
+    if package.pkg_config.is_some() {
+        return vec![format!("PkgConfig::{}", pkg_config_prefix(&package.name))];
+    }
+    vec![package.name.clone()]
+}
+
+// バックエンドのパッケージ名、find_package に渡す名前、リンクするターゲット
+pub type KnownPackage = (&'static str, &'static str, &'static [&'static str]);
+
+// バックエンドのパッケージの find_package 名とリンクするターゲット
+// 既知のパッケージ以外は "<名前>::<名前>" とし、cpam.toml の targets で上書きできる
+pub fn backend_targets(known: &[KnownPackage], name: &str, dependency: &Dependency) -> (String, Vec<String>) {
+    let known = known.iter().find(|(known_name, _, _)| *known_name == name);
+    let package = known.map(|(_, package, _)| package.to_string()).unwrap_or_else(|| name.to_string());
+    let targets = match (dependency.targets(), known) {
+        (Some(targets), _) => targets.to_vec(),
+        (None, Some((_, _, targets))) => targets.iter().map(|target| target.to_string()).collect(),
+        (None, None) => vec![format!("{}::{}", name, name)],
+    };
+    (package, targets)
+}
+
+// pkg_check_modules の結果の変数名に使う接頭辞
+fn pkg_config_prefix(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect()
+}
+
+// cpam.toml からの相対パスを CMake に渡せる絶対パスへ変換する
+fn resolve_local_path(name: &str, path: &str) -> io::Result<String> {
+    let path = Path::new(path);
+    if !path.is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("依存ライブラリ '{}' のパス '{}' が存在しません。", name, path.display()),
+        ));
+    }
+
+    let absolute = std::path::absolute(path)?;
+    Ok(absolute.to_string_lossy().replace('\\', "/"))
+}
+
+// 依存関係用CMakeファイルをビルドディレクトリへ書き出す
+pub fn write_dependencies(
+    config: &CpamConfig,
+    lockfile: &Lockfile,
+    sources: &BTreeMap<String, PathBuf>,
+    enabled_features: &BTreeSet<String>,
+    build_dir: &Path,
+) -> io::Result<()> {
+    let content = render_dependencies(config, lockfile, sources, enabled_features)?;
+    fs::write(build_dir.join(DEPS_FILE_NAME), content)
+}
+
+// CMakeLists.txt が生成ファイルを取り込んでいるか確認する
+pub fn includes_dependencies(source_dir: &Path) -> bool {
+    match fs::read_to_string(source_dir.join("CMakeLists.txt")) {
+        Ok(content) => content.contains(DEPS_FILE_NAME),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn without_options_keeps_the_line() {
+        assert_eq!(with_options(&[], "FetchContent_MakeAvailable(fmt)"), "FetchContent_MakeAvailable(fmt)");
+    }
+
+    #[test]
+    fn options_restore_previous_values() {
+        let lines = with_options(&[("BUILD_TESTING", "OFF".to_string())], "FetchContent_MakeAvailable(fmt)");
+        assert_eq!(
+            lines,
+            "if(DEFINED BUILD_TESTING)\n    set(CPAM_SAVED_BUILD_TESTING \"${BUILD_TESTING}\")\nendif()\n\
+             set(BUILD_TESTING \"OFF\")\n\
+             FetchContent_MakeAvailable(fmt)\n\
+             if(DEFINED CPAM_SAVED_BUILD_TESTING)\n    set(BUILD_TESTING \"${CPAM_SAVED_BUILD_TESTING}\")\n    unset(CPAM_SAVED_BUILD_TESTING)\nelse()\n    unset(BUILD_TESTING)\nendif()"
+        );
+    }
+
+    #[test]
+    fn option_values_are_escaped() {
+        let lines = with_options(&[("FOO_PATH", "C:\\dir \"x\"".to_string())], "add_subdirectory(foo)");
+        assert!(lines.contains("set(FOO_PATH \"C:\\\\dir \\\"x\\\"\")\n"));
+    }
+
+    #[test]
+    fn backend_build_dependencies_are_not_found() {
+        for backend in ["vcpkg", "conan"] {
+            let config: CpamConfig = toml::from_str(&format!(
+                "[build]\nbackend = \"{}\"\n\n[dependencies]\nfmt = \"10\"\n\n[build-dependencies]\nprotobuf = \"3\"\n",
+                backend
+            ))
+            .unwrap();
+            let content = render_packages(&config, &Lockfile::default(), &BTreeMap::new()).unwrap();
+            assert!(content.contains("find_package(fmt REQUIRED)"), "{}", content);
+            assert!(!content.contains("protobuf"), "{}", content);
+        }
+    }
+
+    #[test]
+    fn conditional_indents_the_block() {
+        assert_eq!(conditional(Some("WIN32"), "a\nb"), "if(WIN32)\n    a\n    b\nendif()\n");
+        assert_eq!(conditional(None, "a"), "a\n");
+    }
+}
@@ -0,0 +1,668 @@
+use crate::cmake;
+use crate::config::{CpamConfig, ProjectConfig};
+use crate::standard;
+use crate::workspace::Workspace;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+pub const CMAKELISTS_NAME: &str = "CMakeLists.txt";
+
+// cpam が管理するブロックの区切り（この間は cpam build のたびに cpam.toml から書き直す）
+pub const BEGIN_MARKER: &str = "# ---- cpam:begin ----";
+pub const END_MARKER: &str = "# ---- cpam:end ----";
+const MANAGED_NOTE: &str = "# この区切りの間は cpam build が cpam.toml から再生成します。編集は区切りの外に書いてください。";
+
+// テストや examples を作る条件（他のプロジェクトから取り込まれた場合は作らず、ワークスペースのメンバーは作る）
+const TOP_LEVEL_CONDITION: &str = "CMAKE_SOURCE_DIR STREQUAL CMAKE_CURRENT_SOURCE_DIR OR CPAM_WORKSPACE_MEMBER";
+
+// 新しく作る CMakeLists.txt の、管理ブロックの後に置くユーザー用の欄
+const USER_SECTION: &str = "\n# ここから下は自由に編集できます（cpam build は変更しません）。\n";
+
+// 言語ごとのソースファイルの拡張子（先頭が main ファイルの拡張子）
+fn source_extensions(language: &str) -> &'static [&'static str] {
+    match language {
+        "c" => &["c"],
+        "cuda" => &["cu", "cpp"],
+        _ => &["cpp", "cc", "cxx"],
+    }
+}
+
+// project() に渡す言語
+fn project_languages(language: &str) -> &'static str {
+    match language {
+        "c" => "C",
+        "cuda" => "CUDA CXX",
+        _ => "CXX",
+    }
+}
+
+// cpam.toml の内容から管理ブロックの中身を生成する
+pub fn render(config: &CpamConfig, source_dir: &Path) -> io::Result<String> {
+    let project = config.project.as_ref().ok_or_else(|| Error::new(
+        ErrorKind::InvalidData,
+        "cpam.toml に [project] がありません。",
+    ))?;
+
+    let mut content = String::new();
+    let cmake_version = standard::required_cmake_version(&project.standard()?);
+    content.push_str(&format!("cmake_minimum_required(VERSION {})\n", cmake_version));
+    content.push_str(&format!("project({} LANGUAGES {})\n", project.name, project_languages(&project.language)));
+
+    if project.is_library() {
+        content.push_str(&render_library(&project.name, project.library_type()?, source_dir, &project.language)?);
+        content.push_str(&render_standard(project, &project.name)?);
+    } else {
+        let sources = sources(source_dir, &project.language)?;
+        content.push_str(&format!("\nadd_executable({}\n", project.name));
+        for source in &sources {
+            content.push_str(&format!("    {}\n", source));
+        }
+        content.push_str(")\n");
+        if source_dir.join("include").is_dir() {
+            content.push_str(&format!("target_include_directories({} PRIVATE include)\n", project.name));
+        }
+        content.push_str(&render_standard(project, &project.name)?);
+    }
+
+    content.push_str("\n# cpam.toml の依存関係（cpam build が生成）\n");
+    content.push_str(cmake::DEPS_INCLUDE_LINE);
+    content.push('\n');
+
+    content.push_str(&render_targets(config, project, source_dir)?);
+    if project.is_library() {
+        content.push_str(&render_tests(&project.name, &cmake::dev_dependencies_target(config), source_dir, &project.language)?);
+    }
+    Ok(content)
+}
+
+// render が作るビルド可能なターゲットの名前（本体、追加の実行ファイル、テスト）
+// ヘッダーだけのライブラリはビルドするものが無いので含めない
+pub fn build_targets(config: &CpamConfig, source_dir: &Path) -> io::Result<Vec<String>> {
+    let project = config.project.as_ref().ok_or_else(|| Error::new(
+        ErrorKind::InvalidData,
+        "cpam.toml に [project] がありません。",
+    ))?;
+
+    let mut names = Vec::new();
+    if !project.is_library() || project.library_type()? != "interface" {
+        names.push(project.name.clone());
+    }
+    names.extend(targets(config, source_dir)?.into_iter().map(|target| target.name));
+    if project.is_library() {
+        names.extend(test_sources(source_dir, &project.language)?.into_iter().map(|(test, _)| test));
+    }
+    Ok(names)
+}
+
+// プロジェクト本体の他に作る実行ファイルの種類
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetKind {
+    Bin,
+    Example,
+    Bench,
+}
+
+impl TargetKind {
+    pub const ALL: [TargetKind; 3] = [TargetKind::Bin, TargetKind::Example, TargetKind::Bench];
+
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            TargetKind::Bin => "bin",
+            TargetKind::Example => "example",
+            TargetKind::Bench => "bench",
+        }
+    }
+
+    // 自動で実行ファイルを探すディレクトリ
+    fn directory(&self) -> &'static str {
+        match self {
+            TargetKind::Bin => "src/bin",
+            TargetKind::Example => "examples",
+            TargetKind::Bench => "benches",
+        }
+    }
+}
+
+// 追加の実行ファイル
+pub struct Target {
+    pub kind: TargetKind,
+    pub name: String,
+    pub sources: Vec<String>,
+}
+
+// [[bin]], [[example]], [[bench]] と src/bin/, examples/, benches/ から見つけた実行ファイル
+// ディレクトリ直下の <名前>.c や <名前>/ のソースが1つの実行ファイルになり、cpam.toml の指定が優先される
+pub fn targets(config: &CpamConfig, source_dir: &Path) -> io::Result<Vec<Target>> {
+    let project = config.project.as_ref();
+    let extensions = source_extensions(project.map(|project| project.language.as_str()).unwrap_or("cpp"));
+    let mut targets: Vec<Target> = Vec::new();
+
+    for kind in TargetKind::ALL {
+        let configured = match kind {
+            TargetKind::Bin => &config.bins,
+            TargetKind::Example => &config.examples,
+            TargetKind::Bench => &config.benches,
+        };
+
+        let mut kind_targets = Vec::new();
+        for target in configured.iter().flatten() {
+            let sources = match &target.path {
+                Some(path) => target_sources(source_dir, Path::new(path), extensions)?,
+                None => default_sources(source_dir, kind, &target.name, extensions)?,
+            };
+            if sources.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("[[{}]] '{}' のソースファイルが見つかりません。", kind.table_name(), target.name),
+                ));
+            }
+            kind_targets.push(Target { kind, name: target.name.clone(), sources });
+        }
+
+        let entries = match fs::read_dir(source_dir.join(kind.directory())) {
+            Ok(entries) => entries.collect::<io::Result<Vec<_>>>()?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut discovered = Vec::new();
+        for entry in entries {
+            let path = entry.path();
+            let name = if path.is_dir() { path.file_name() } else { path.file_stem() };
+            let name = name.map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let relative = path.strip_prefix(source_dir).unwrap_or(&path);
+            let sources = target_sources(source_dir, relative, extensions)?;
+            // cpam.toml に書かれた実行ファイルのソースは別の実行ファイルにしない
+            let configured = kind_targets
+                .iter()
+                .any(|target: &Target| target.name == name || target.sources.iter().any(|source| sources.contains(source)));
+            if !sources.is_empty() && !configured {
+                discovered.push(Target { kind, name, sources });
+            }
+        }
+        discovered.sort_by(|a, b| a.name.cmp(&b.name));
+        kind_targets.extend(discovered);
+
+        for target in kind_targets {
+            let duplicate = project.is_some_and(|project| project.name == target.name)
+                || targets.iter().any(|other| other.name == target.name);
+            if duplicate {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("実行ファイル名 '{}'（{}）が他のターゲットと重複しています。", target.name, kind.table_name()),
+                ));
+            }
+            targets.push(target);
+        }
+    }
+    Ok(targets)
+}
+
+// ファイルならそのファイル、ディレクトリなら中のソースファイル
+fn target_sources(source_dir: &Path, path: &Path, extensions: &[&str]) -> io::Result<Vec<String>> {
+    let full_path = source_dir.join(path);
+    let mut sources = Vec::new();
+    if full_path.is_dir() {
+        collect_sources(source_dir, &full_path, extensions, &mut sources)?;
+        sources.sort();
+    } else if full_path.is_file() {
+        sources.push(path.to_string_lossy().replace('\\', "/"));
+    }
+    Ok(sources)
+}
+
+// path を省略した場合は <ディレクトリ>/<名前>.<拡張子> か <ディレクトリ>/<名前>/ を探す
+fn default_sources(source_dir: &Path, kind: TargetKind, name: &str, extensions: &[&str]) -> io::Result<Vec<String>> {
+    let directory = Path::new(kind.directory());
+    for extension in extensions {
+        let path = directory.join(format!("{}.{}", name, extension));
+        if source_dir.join(&path).is_file() {
+            return target_sources(source_dir, &path, extensions);
+        }
+    }
+    target_sources(source_dir, &directory.join(name), extensions)
+}
+
+// 追加の実行ファイルのターゲット
+// ライブラリのプロジェクトではライブラリを、実行ファイルのプロジェクトでは依存ライブラリをリンクする
+// examples と benches は dev-dependencies も使え、他のプロジェクトから取り込まれた場合は作らない
+fn render_targets(config: &CpamConfig, project: &ProjectConfig, source_dir: &Path) -> io::Result<String> {
+    let targets = targets(config, source_dir)?;
+    let dependencies_target = cmake::dependencies_target(config);
+    let dev_dependencies_target = cmake::dev_dependencies_target(config);
+    let mut content = String::new();
+    for kind in TargetKind::ALL {
+        let kind_targets: Vec<&Target> = targets.iter().filter(|target| target.kind == kind).collect();
+        if kind_targets.is_empty() {
+            continue;
+        }
+
+        let top_level_only = kind != TargetKind::Bin;
+        let indent = if top_level_only { "    " } else { "" };
+        content.push_str(&format!("\n# {}/ と [[{}]] の実行ファイル\n", kind.directory(), kind.table_name()));
+        if top_level_only {
+            content.push_str(&format!("if({})\n", TOP_LEVEL_CONDITION));
+        }
+
+        for target in kind_targets {
+            let mut lines = format!("add_executable({}\n", target.name);
+            for source in &target.sources {
+                lines.push_str(&format!("    {}\n", source));
+            }
+            lines.push_str(")\n");
+
+            let mut links = Vec::new();
+            if project.is_library() {
+                lines.push_str(&format!("target_link_libraries({} PRIVATE {})\n", target.name, project.name));
+            } else {
+                if source_dir.join("include").is_dir() {
+                    lines.push_str(&format!("target_include_directories({} PRIVATE include)\n", target.name));
+                }
+                links.push(&dependencies_target);
+            }
+            if top_level_only {
+                links.push(&dev_dependencies_target);
+            }
+            for link in links {
+                lines.push_str(&format!(
+                    "if(TARGET {})\n    target_link_libraries({} PRIVATE {})\nendif()\n",
+                    link, target.name, link
+                ));
+            }
+            lines.push_str(&render_standard(project, &target.name)?);
+
+            for line in lines.lines() {
+                content.push_str(&format!("{}{}\n", indent, line));
+            }
+        }
+
+        if top_level_only {
+            content.push_str("endif()\n");
+        }
+    }
+    Ok(content)
+}
+
+// 言語の規格をターゲットに設定する
+// ライブラリは利用側にも同じ規格以上を要求する（ヘッダーが規格の機能を使うことがあるため）
+fn render_standard(project: &ProjectConfig, target: &str) -> io::Result<String> {
+    let Some(standard) = project.standard()? else {
+        return Ok(String::new());
+    };
+
+    // CUDA のプロジェクトは .cpp も同じ規格でコンパイルする
+    let (languages, features): (&[&str], &[&str]) = match project.language.as_str() {
+        "c" => (&["C"], &["c_std"]),
+        "cuda" => (&["CUDA", "CXX"], &["cuda_std", "cxx_std"]),
+        _ => (&["CXX"], &["cxx_std"]),
+    };
+    let features: Vec<String> = features.iter().map(|feature| format!("{}_{}", feature, standard.version)).collect();
+    let library = project.is_library() && target == project.name;
+    let interface = library && project.library_type()? == "interface";
+
+    let mut content = String::new();
+    if !interface {
+        let properties: Vec<String> = languages
+            .iter()
+            .map(|language| format!(
+                "    {}_STANDARD {}\n    {}_STANDARD_REQUIRED ON\n    {}_EXTENSIONS {}\n",
+                language, standard.version, language, language, if standard.extensions { "ON" } else { "OFF" }
+            ))
+            .collect();
+        content.push_str(&format!("set_target_properties({} PROPERTIES\n{})\n", target, properties.concat()));
+    }
+    if library {
+        let scope = if interface { "INTERFACE" } else { "PUBLIC" };
+        content.push_str(&format!("target_compile_features({} {} {})\n", target, scope, features.join(" ")));
+    }
+    Ok(content)
+}
+
+// ライブラリのターゲット
+// 公開ヘッダーの include/ は利用側にも見せ、src/ の内部ヘッダーはライブラリ自身だけが使う
+fn render_library(name: &str, library_type: &str, source_dir: &Path, language: &str) -> io::Result<String> {
+    let mut content = String::new();
+    if library_type == "interface" {
+        content.push_str(&format!("\nadd_library({} INTERFACE)\n", name));
+        content.push_str(&format!("add_library({}::{} ALIAS {})\n", name, name, name));
+        content.push_str(&format!(
+            "target_include_directories({} INTERFACE $<BUILD_INTERFACE:${{CMAKE_CURRENT_SOURCE_DIR}}/include>)\n",
+            name
+        ));
+        return Ok(content);
+    }
+
+    content.push_str(&format!("\nadd_library({} {}\n", name, library_type.to_uppercase()));
+    let mut sources = Vec::new();
+    collect_sources(source_dir, &source_dir.join("src"), source_extensions(language), &mut sources)?;
+    sources.retain(|source| !source.starts_with("src/bin/"));
+    sources.sort();
+    if sources.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("ライブラリ '{}' のソースファイルが src/ にありません（ヘッダーだけなら library_type = \"interface\" にしてください）。", name),
+        ));
+    }
+    for source in &sources {
+        content.push_str(&format!("    {}\n", source));
+    }
+    content.push_str(")\n");
+    content.push_str(&format!("add_library({}::{} ALIAS {})\n", name, name, name));
+    content.push_str(&format!(
+        "target_include_directories({}\n    PUBLIC $<BUILD_INTERFACE:${{CMAKE_CURRENT_SOURCE_DIR}}/include>\n    PRIVATE ${{CMAKE_CURRENT_SOURCE_DIR}}/src\n)\n",
+        name
+    ));
+    // Windows では共有ライブラリの全シンボルを公開する（__declspec(dllexport) を書かなくてよい）
+    if library_type == "shared" {
+        content.push_str(&format!("set_target_properties({} PROPERTIES WINDOWS_EXPORT_ALL_SYMBOLS ON)\n", name));
+    }
+    Ok(content)
+}
+
+// tests/ のファイルごとに、ライブラリを使うテストの実行ファイルを作る
+// 他のプロジェクトから依存ライブラリとして取り込まれた場合は作らない
+fn render_tests(name: &str, dev_dependencies_target: &str, source_dir: &Path, language: &str) -> io::Result<String> {
+    let tests = test_sources(source_dir, language)?;
+    if tests.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut content = format!("\n# tests/ のテスト（ctest で実行）\nif({})\n    enable_testing()\n", TOP_LEVEL_CONDITION);
+    for (test, source) in &tests {
+        content.push_str(&format!("    add_executable({} {})\n", test, source));
+        content.push_str(&format!("    target_link_libraries({} PRIVATE {})\n", test, name));
+        content.push_str(&format!(
+            "    if(TARGET {})\n        target_link_libraries({} PRIVATE {})\n    endif()\n",
+            dev_dependencies_target, test, dev_dependencies_target
+        ));
+        content.push_str(&format!("    add_test(NAME {} COMMAND {})\n", test, test));
+    }
+    content.push_str("endif()\n");
+    Ok(content)
+}
+
+// tests/ 直下のテストの名前とソースファイル（名前順）
+fn test_sources(source_dir: &Path, language: &str) -> io::Result<Vec<(String, String)>> {
+    let extensions: &[&str] = match language {
+        "c" => &["c"],
+        _ => &["cpp", "cc", "cxx", "cu"],
+    };
+    let mut tests = Vec::new();
+    let entries = match fs::read_dir(source_dir.join("tests")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(tests),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        if path.is_file() && extensions.contains(&extension) {
+            let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            tests.push((stem, format!("tests/{}", path.file_name().unwrap_or_default().to_string_lossy())));
+        }
+    }
+    tests.sort();
+    Ok(tests)
+}
+
+// src/ 以下のソースファイル（無ければ main ファイル）
+fn sources(source_dir: &Path, language: &str) -> io::Result<Vec<String>> {
+    let extensions = source_extensions(language);
+    let mut sources = Vec::new();
+    collect_sources(source_dir, &source_dir.join("src"), extensions, &mut sources)?;
+    sources.retain(|source| !source.starts_with("src/bin/"));
+    sources.sort();
+
+    if sources.is_empty() {
+        sources.push(format!("src/main.{}", extensions[0]));
+    }
+    Ok(sources)
+}
+
+fn collect_sources(source_dir: &Path, dir: &Path, extensions: &[&str], sources: &mut Vec<String>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(source_dir, &path, extensions, sources)?;
+            continue;
+        }
+
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        if extensions.contains(&extension) {
+            let relative = path.strip_prefix(source_dir).unwrap_or(&path);
+            sources.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+// CMakeLists.txt の管理ブロックを書き直す（区切りの外は変更しない）
+// 無ければ新しく作り、区切りの無い手書きの CMakeLists.txt は変更せずに区切りの追加を促す
+pub fn update(config: &CpamConfig, source_dir: &Path) -> io::Result<()> {
+    write_managed(source_dir, &render(config, source_dir)?)
+}
+
+// ワークスペースのルートの CMakeLists.txt の管理ブロックを書き直す
+pub fn update_workspace(workspace: &Workspace, source_dir: &Path) -> io::Result<()> {
+    write_managed(source_dir, &render_workspace(workspace))
+}
+
+// ワークスペースのルートの管理ブロック
+// 全メンバーが共有する依存ライブラリを取り込んでから、依存される側のメンバーから順に追加する
+pub fn render_workspace(workspace: &Workspace) -> String {
+    let mut languages: Vec<&str> = Vec::new();
+    for member in &workspace.members {
+        let language = member.config.project.as_ref().map(|project| project.language.as_str()).unwrap_or_default();
+        for language in project_languages(language).split(' ') {
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+    }
+
+    // メンバーの中で最も新しい規格に合わせる（規格の誤りは各メンバーの生成時に報告する）
+    let standards: Vec<standard::LanguageStandard> = workspace
+        .members
+        .iter()
+        .filter_map(|member| member.config.project.as_ref()?.standard().ok()?)
+        .collect();
+
+    let mut content = String::new();
+    content.push_str(&format!("cmake_minimum_required(VERSION {})\n", standard::required_cmake_version(&standards)));
+    content.push_str(&format!("project({} LANGUAGES {})\n", workspace.name(), languages.join(" ")));
+    content.push_str("enable_testing()\n");
+
+    content.push_str("\n# メンバーが共有する依存ライブラリ（cpam build が生成）\n");
+    content.push_str(cmake::DEPS_INCLUDE_LINE);
+    content.push('\n');
+
+    // メンバーのテストと examples は、ワークスペースから追加した場合も作る
+    content.push_str("\n# ワークスペースのメンバー\nset(CPAM_WORKSPACE_MEMBER ON)\n");
+    for member in &workspace.members {
+        content.push_str(&format!("add_subdirectory({})\n", member.dir));
+    }
+    content.push_str("unset(CPAM_WORKSPACE_MEMBER)\n");
+    content
+}
+
+// 管理ブロックを生成した内容に置き換える
+fn write_managed(source_dir: &Path, managed: &str) -> io::Result<()> {
+    let path = source_dir.join(CMAKELISTS_NAME);
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let block = format!("{}\n{}\n{}{}\n", BEGIN_MARKER, MANAGED_NOTE, managed, END_MARKER);
+    let content = match &existing {
+        None => format!("{}{}", block, USER_SECTION),
+        Some(existing) => match managed_range(existing) {
+            Some((begin, end)) => format!("{}{}{}", &existing[..begin], block, &existing[end..]),
+            None => {
+                print_missing_markers(if source_dir == Path::new(".") { Path::new(CMAKELISTS_NAME) } else { &path });
+                return Ok(());
+            }
+        },
+    };
+
+    if existing.as_deref() != Some(content.as_str()) {
+        fs::write(&path, content)?;
+        if source_dir == Path::new(".") {
+            println!("{} を更新しました。", CMAKELISTS_NAME);
+        } else {
+            println!("{} を更新しました。", path.display());
+        }
+    }
+    Ok(())
+}
+
+// 区切りの無い CMakeLists.txt に cpam.toml が反映されないことを知らせる
+fn print_missing_markers(path: &Path) {
+    println!("警告: {} に管理ブロックの区切りが無いため、cpam.toml の内容（ソース、規格、ターゲットなど）を反映しません。", path.display());
+    println!("反映するには、cmake_minimum_required や project、add_executable などの cpam が生成する部分を削除し、その位置に次の2行を追加してください:");
+    println!("  {}", BEGIN_MARKER);
+    println!("  {}", END_MARKER);
+    println!("次の cpam build で区切りの間が生成され、区切りの外はそのまま残ります。");
+}
+
+// 管理ブロック（開始の区切りの行頭から終了の区切りの改行まで）の範囲
+// 改行が CRLF のファイルでは \r\n まで含める
+fn managed_range(content: &str) -> Option<(usize, usize)> {
+    let begin = content.find(BEGIN_MARKER)?;
+    let mut end = begin + content[begin..].find(END_MARKER)? + END_MARKER.len();
+    if content[end..].starts_with("\r\n") {
+        end += 2;
+    } else if content[end..].starts_with('\n') {
+        end += 1;
+    }
+    Some((begin, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn config(content: &str) -> CpamConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    const BIN_PROJECT: &str = "[project]\nname = \"app\"\nlanguage = \"cpp\"\nbuild_tool = \"make\"\nproject_type = \"bin\"\n";
+
+    #[test]
+    fn renders_executable_with_sorted_sources_and_deps_include() {
+        let dir = TempDir::new("bin");
+        write(&dir, "src/main.cpp", "");
+        write(&dir, "src/util/helper.cpp", "");
+        write(&dir, "include/app.hpp", "");
+
+        let content = render(&config(BIN_PROJECT), &dir).unwrap();
+        assert!(content.starts_with("cmake_minimum_required(VERSION 3.14)\nproject(app LANGUAGES CXX)\n"));
+        assert!(content.contains("add_executable(app\n    src/main.cpp\n    src/util/helper.cpp\n)\n"));
+        assert!(content.contains("target_include_directories(app PRIVATE include)\n"));
+        assert!(content.contains(cmake::DEPS_INCLUDE_LINE));
+    }
+
+    #[test]
+    fn renders_standard_for_library_users() {
+        let dir = TempDir::new("lib");
+        write(&dir, "src/lib.cpp", "");
+        let config = config(
+            "[project]\nname = \"mylib\"\nlanguage = \"cpp\"\nbuild_tool = \"make\"\nproject_type = \"lib\"\nlibrary_type = \"static\"\nstandard = \"gnu++17\"\n",
+        );
+
+        let content = render(&config, &dir).unwrap();
+        assert!(content.contains("add_library(mylib STATIC\n    src/lib.cpp\n)\n"));
+        assert!(content.contains("    CXX_STANDARD 17\n    CXX_STANDARD_REQUIRED ON\n    CXX_EXTENSIONS ON\n"));
+        assert!(content.contains("target_compile_features(mylib PUBLIC cxx_std_17)\n"));
+    }
+
+    #[test]
+    fn build_targets_include_extra_executables_and_tests() {
+        let dir = TempDir::new("build-targets");
+        write(&dir, "include/mylib.hpp", "");
+        write(&dir, "src/bin/tool.cpp", "");
+        write(&dir, "examples/demo.cpp", "");
+        write(&dir, "tests/basic.cpp", "");
+        let library = "[project]\nname = \"mylib\"\nlanguage = \"cpp\"\nbuild_tool = \"make\"\nproject_type = \"lib\"\n";
+
+        let interface = config(&format!("{}library_type = \"interface\"\n", library));
+        assert_eq!(build_targets(&interface, &dir).unwrap(), vec!["tool", "demo", "basic"]);
+
+        write(&dir, "src/lib.cpp", "");
+        let static_library = config(&format!("{}library_type = \"static\"\n", library));
+        assert_eq!(build_targets(&static_library, &dir).unwrap(), vec!["mylib", "tool", "demo", "basic"]);
+    }
+
+    #[test]
+    fn raises_cmake_minimum_for_newer_standards() {
+        let dir = TempDir::new("cxx26");
+        write(&dir, "src/main.cpp", "");
+        let config = config(&format!("{}standard = \"c++26\"\n", BIN_PROJECT));
+
+        let content = render(&config, &dir).unwrap();
+        assert!(content.starts_with("cmake_minimum_required(VERSION 3.25)\n"));
+    }
+
+    #[test]
+    fn creates_new_file_with_markers_and_user_section() {
+        let dir = TempDir::new("create");
+        write_managed(&dir, "project(app)\n").unwrap();
+
+        let content = fs::read_to_string(dir.join(CMAKELISTS_NAME)).unwrap();
+        assert!(content.starts_with(&format!("{}\n{}\nproject(app)\n{}\n", BEGIN_MARKER, MANAGED_NOTE, END_MARKER)));
+        assert!(content.ends_with(USER_SECTION));
+    }
+
+    #[test]
+    fn replaces_only_the_managed_block() {
+        let dir = TempDir::new("replace");
+        let existing = format!("# before\n{}\nold()\n{}\ntarget_compile_options(app PRIVATE -Wall)\n", BEGIN_MARKER, END_MARKER);
+        write(&dir, CMAKELISTS_NAME, &existing);
+
+        write_managed(&dir, "new()\n").unwrap();
+        let content = fs::read_to_string(dir.join(CMAKELISTS_NAME)).unwrap();
+        assert_eq!(
+            content,
+            format!("# before\n{}\n{}\nnew()\n{}\ntarget_compile_options(app PRIVATE -Wall)\n", BEGIN_MARKER, MANAGED_NOTE, END_MARKER)
+        );
+    }
+
+    #[test]
+    fn leaves_file_without_markers_untouched() {
+        let dir = TempDir::new("unmanaged");
+        write(&dir, CMAKELISTS_NAME, "project(handwritten)\n");
+
+        write_managed(&dir, "new()\n").unwrap();
+        assert_eq!(fs::read_to_string(dir.join(CMAKELISTS_NAME)).unwrap(), "project(handwritten)\n");
+    }
+
+    #[test]
+    fn managed_range_requires_both_markers() {
+        let content = format!("a\n{}\nb\n{}\nc\n", BEGIN_MARKER, END_MARKER);
+        let (begin, end) = managed_range(&content).unwrap();
+        assert_eq!(&content[..begin], "a\n");
+        assert_eq!(&content[end..], "c\n");
+
+        let content = format!("a\r\n{}\r\nb\r\n{}\r\nc\r\n", BEGIN_MARKER, END_MARKER);
+        let (begin, end) = managed_range(&content).unwrap();
+        assert_eq!(&content[..begin], "a\r\n");
+        assert_eq!(&content[end..], "c\r\n");
+
+        assert_eq!(managed_range(&format!("{}\nb\n", BEGIN_MARKER)), None);
+        assert_eq!(managed_range("project(app)\n"), None);
+    }
+}
@@ -1,36 +1,293 @@
-use std::collections::HashMap;
-
+use std::io::{self, Error, ErrorKind};
 use std::path::Path;
 use crate::cli::AddArgs;
-use crate::config::CpamConfig;
+use crate::config::{self, Backend, CpamConfig, Dependency, DependencyDetail, DependencyKind};
+use crate::fetch;
+use crate::platform;
+use crate::registry;
+use crate::version;
+use semver::VersionReq;
+
+// アーカイブとして扱う拡張子
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tar.gz", ".tgz", ".tar.xz", ".tar.bz2", ".zip"];
 
-pub fn execute(args: &AddArgs) {
+pub fn execute(args: &AddArgs) -> Result<(), String> {
     let toml_path = Path::new("cpam.toml");
     if !toml_path.exists() {
-        eprintln!("cpam.toml がカレントディレクトリに存在しません。");
-        return;
+        return Err("cpam.toml がカレントディレクトリに存在しません。".to_string());
     }
 
     // 現在の設定を読み込む
     let mut config = match CpamConfig::load() {
         Ok(config) => config,
         Err(e) => {
-            eprintln!("cpam.toml の読み込みに失敗: {}", e);
-            return;
+            return Err(format!("cpam.toml の読み込みに失敗: {}", e));
+        }
+    };
+
+    let dependency = match build_dependency(args, &config) {
+        Ok(dependency) => dependency,
+        Err(e) if fetch::is_checksum_mismatch(&e) => {
+            return Err(format!("アーカイブの整合性検証に失敗: {}", e));
+        }
+        Err(e) => {
+            return Err(format!("依存ライブラリの指定が不正です: {}", e));
         }
     };
 
+    let kind = if args.dev {
+        DependencyKind::Dev
+    } else if args.build {
+        DependencyKind::Build
+    } else {
+        DependencyKind::Normal
+    };
+
+    // 他のテーブルにある同名の依存ライブラリは移動する
+    for other in DependencyKind::ALL.into_iter().filter(|other| *other != kind) {
+        if let Some(dependencies) = config.dependency_table_mut(other) {
+            if dependencies.remove(&args.name).is_some() {
+                println!("'{}' を [{}] から [{}] へ移動します。", args.name, other.table_name(), kind.table_name());
+            }
+        }
+    }
+
     // 依存関係を追加
-    let mut dependencies = config.dependencies.unwrap_or_else(|| HashMap::new());
-    let version = args.version.clone().unwrap_or_else(|| "*".to_string());
-    dependencies.insert(args.name.clone(), version);
-    config.dependencies = Some(dependencies);
+    config.dependency_table_mut(kind).get_or_insert_with(Default::default).insert(args.name.clone(), dependency);
 
     // 設定を保存
     if let Err(e) = config.save() {
-        eprintln!("cpam.toml の更新に失敗: {}", e);
-        return;
+        return Err(format!("cpam.toml の更新に失敗: {}", e));
     }
 
     println!("依存ライブラリ '{}' を追加しました。", args.name);
+    Ok(())
+}
+
+// コマンドライン引数から cpam.toml に書き込む依存関係を組み立てる
+fn build_dependency(args: &AddArgs, config: &CpamConfig) -> io::Result<Dependency> {
+    if let Some(platforms) = platforms(args) {
+        platform::validate(&platforms)?;
+    }
+    let has_git_reference = args.tag.is_some() || args.branch.is_some() || args.rev.is_some();
+
+    let source = match &args.source {
+        Some(source) => source,
+        None => {
+            if has_git_reference || args.sha256.is_some() {
+                return Err(Error::new(ErrorKind::InvalidInput, "--tag, --branch, --rev, --sha256 は --source と一緒に指定してください。"));
+            }
+            if args.system || args.find_package.is_some() || args.pkg_config.is_some() {
+                return system_dependency(args);
+            }
+            // vcpkg や Conan のバックエンドではパッケージの検索をバックエンドに任せる
+            if args.registry.is_none() && config.backend()? != Backend::Cpam {
+                return backend_dependency(args);
+            }
+            return registry_dependency(args, config);
+        }
+    };
+
+    let version = args.version.clone().unwrap_or_else(|| "*".to_string());
+    version::parse_requirement(&version)?;
+
+    let mut detail = DependencyDetail {
+        version: Some(version),
+        targets: targets(args),
+        platforms: platforms(args),
+        optional: args.optional.then_some(true),
+        ..Default::default()
+    };
+
+    match SourceKind::detect(source) {
+        SourceKind::Archive => {
+            if has_git_reference {
+                return Err(Error::new(ErrorKind::InvalidInput, "アーカイブには --tag, --branch, --rev を指定できません。"));
+            }
+            detail.url = Some(source.clone());
+            detail.sha256 = Some(archive_sha256(source, args.sha256.as_deref())?);
+        }
+        SourceKind::Git => {
+            if args.sha256.is_some() {
+                return Err(Error::new(ErrorKind::InvalidInput, "--sha256 はアーカイブURLにのみ指定できます。"));
+            }
+            detail.git = Some(source.clone());
+            detail.tag = args.tag.clone();
+            detail.branch = args.branch.clone();
+            detail.rev = args.rev.clone();
+        }
+        SourceKind::Path => {
+            if has_git_reference || args.sha256.is_some() {
+                return Err(Error::new(ErrorKind::InvalidInput, "ローカルパスには --tag, --branch, --rev, --sha256 を指定できません。"));
+            }
+            if !Path::new(source).is_dir() {
+                return Err(Error::new(ErrorKind::NotFound, format!("ディレクトリ '{}' が存在しません。", source)));
+            }
+            detail.path = Some(source.replace('\\', "/"));
+        }
+    }
+
+    Ok(Dependency::Detailed(Box::new(detail)))
+}
+
+// アーカイブをダウンロードして sha256 を求める（指定があればそれと照合する）
+fn archive_sha256(url: &str, expected: Option<&str>) -> io::Result<String> {
+    if let Some(expected) = expected {
+        config::validate_sha256(expected)?;
+    }
+
+    let archive = fetch::download_archive(url)?;
+    match expected {
+        Some(expected) => {
+            fetch::verify_archive(url, &archive, expected)?;
+            Ok(expected.to_lowercase())
+        }
+        None => {
+            let sha256 = fetch::sha256_of_file(&archive)?;
+            println!("sha256 を記録しました: {}", sha256);
+            Ok(sha256)
+        }
+    }
+}
+
+// レジストリに登録されたパッケージとして依存関係を組み立てる
+fn registry_dependency(args: &AddArgs, config: &CpamConfig) -> io::Result<Dependency> {
+    let registries = registry::configured(config)?;
+    let (registry, entry) = registry::find(&registries, &args.name, args.registry.as_deref())?;
+
+    // バージョン省略時は最新バージョンと互換性のある範囲を要求する
+    let version = match &args.version {
+        Some(version) => version.clone(),
+        None => {
+            let (_, latest) = entry
+                .select(&VersionReq::STAR)
+                .ok_or_else(|| version::unsatisfiable(&args.name, "*", &entry.version_names()))?;
+            format!("^{}", latest)
+        }
+    };
+
+    let requirement = version::parse_requirement(&version)?;
+    if entry.select(&requirement).is_none() {
+        return Err(version::unsatisfiable(&args.name, &version, &entry.version_names()));
+    }
+    println!("レジストリ '{}' で '{}' が見つかりました。", registry.name, args.name);
+
+    if args.registry.is_none() && args.targets.is_empty() && args.platforms.is_empty() && !args.optional {
+        return Ok(Dependency::Version(version));
+    }
+    Ok(Dependency::Detailed(Box::new(DependencyDetail {
+        version: Some(version),
+        registry: args.registry.clone(),
+        targets: targets(args),
+        platforms: platforms(args),
+        optional: args.optional.then_some(true),
+        ..Default::default()
+    })))
+}
+
+// vcpkg や Conan がインストールするパッケージとして依存関係を組み立てる（レジストリは調べない）
+fn backend_dependency(args: &AddArgs) -> io::Result<Dependency> {
+    let version = args.version.clone().unwrap_or_else(|| "*".to_string());
+    version::parse_requirement(&version)?;
+
+    if args.targets.is_empty() && args.platforms.is_empty() && !args.optional {
+        return Ok(Dependency::Version(version));
+    }
+    Ok(Dependency::Detailed(Box::new(DependencyDetail {
+        version: Some(version),
+        targets: targets(args),
+        platforms: platforms(args),
+        optional: args.optional.then_some(true),
+        ..Default::default()
+    })))
+}
+
+// システムにインストール済みのライブラリとして依存関係を組み立てる
+fn system_dependency(args: &AddArgs) -> io::Result<Dependency> {
+    if let Some(version) = &args.version {
+        version::parse_requirement(version)?;
+    }
+
+    let mut detail = DependencyDetail {
+        version: args.version.clone(),
+        targets: targets(args),
+        platforms: platforms(args),
+        optional: args.optional.then_some(true),
+        ..Default::default()
+    };
+    match &args.pkg_config {
+        Some(module) => detail.pkg_config = Some(module.clone()),
+        None => {
+            detail.system = Some(true);
+            detail.find_package = Some(args.find_package.clone().unwrap_or_else(|| args.name.clone()));
+        }
+    }
+
+    Ok(Dependency::Detailed(Box::new(detail)))
+}
+
+// --target の指定（無ければ None）
+fn targets(args: &AddArgs) -> Option<Vec<String>> {
+    if args.targets.is_empty() {
+        None
+    } else {
+        Some(args.targets.clone())
+    }
+}
+
+// --platform の指定（無ければ None）
+fn platforms(args: &AddArgs) -> Option<Vec<String>> {
+    if args.platforms.is_empty() {
+        None
+    } else {
+        Some(args.platforms.clone())
+    }
+}
+
+// --source の文字列から取得元の種類を判定する
+enum SourceKind {
+    Git,
+    Path,
+    Archive,
+}
+
+impl SourceKind {
+    fn detect(source: &str) -> Self {
+        let is_url = source.contains("://");
+        if is_url && ARCHIVE_EXTENSIONS.iter().any(|ext| source.ends_with(ext)) {
+            SourceKind::Archive
+        } else if is_url || source.starts_with("git@") || source.ends_with(".git") {
+            SourceKind::Git
+        } else {
+            SourceKind::Path
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn config(content: &str) -> CpamConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    fn args(arguments: &[&str]) -> AddArgs {
+        AddArgs::parse_from(["add"].iter().chain(arguments))
+    }
+
+    #[test]
+    fn vcpkg_backend_adds_ports_without_registry() {
+        let config = config("[build]\nbackend = \"vcpkg\"\n");
+        let dependency = build_dependency(&args(&["zlib"]), &config).unwrap();
+        assert!(matches!(dependency, Dependency::Version(version) if version == "*"));
+    }
+
+    #[test]
+    fn conan_backend_keeps_the_requested_version_without_registry() {
+        let config = config("[build]\nbackend = \"conan\"\n");
+        let dependency = build_dependency(&args(&["fmt", "--version", "^10"]), &config).unwrap();
+        assert!(matches!(dependency, Dependency::Version(version) if version == "^10"));
+    }
 }
@@ -1,12 +1,32 @@
 use crate::cli::BuildArgs;
-use crate::config::CpamConfig;
+use crate::cmake;
+use crate::cmakelists;
+use crate::conan;
+use crate::config::{Backend, CpamConfig};
+use crate::features;
+use crate::fetch;
+use crate::platform;
+use crate::resolve;
+use crate::lockfile::Lockfile;
+use crate::vcpkg;
+use crate::workspace::{self, Workspace};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn execute(args: &BuildArgs) {
+pub fn execute(args: &BuildArgs) -> Result<(), String> {
+    // ワークスペースのメンバーのディレクトリで実行した場合は、ルートからそのメンバーをビルドする
+    let member = match workspace::enter_root() {
+        Ok(member) => member,
+        Err(e) => {
+            return Err(format!("ワークスペースの読み込みに失敗: {}", e));
+        }
+    };
+
     // 設定を読み込む
-    let config = match CpamConfig::load() {
+    let mut config = match CpamConfig::load() {
         Ok(config) => config,
         Err(e) => {
             eprintln!("cpam.toml の読み込みに失敗: {}", e);
@@ -20,11 +40,19 @@ pub fn execute(args: &BuildArgs) {
         }
     };
 
+    if config.workspace.is_some() {
+        return build_workspace(args, config, args.package.as_deref().or(member.as_deref()));
+    }
+    if let Some(package) = &args.package {
+        if config.project.as_ref().map(|project| &project.name) != Some(package) {
+            return Err(format!("-p '{}' を指定しましたが、cpam.toml に [workspace] がありません。", package));
+        }
+    }
+
     // ビルドディレクトリ
     let build_dir = &args.build_dir;
     if let Err(e) = fs::create_dir_all(build_dir) {
-        eprintln!("ビルドディレクトリの作成に失敗: {}", e);
-        return;
+        return Err(format!("ビルドディレクトリの作成に失敗: {}", e));
     }
 
     // ジェネレータ（CMakeのビルドシステム）を決定
@@ -40,10 +68,251 @@ pub fn execute(args: &BuildArgs) {
         ".".to_string()
     };
 
+    fetch::set_offline(args.offline);
+
+    // 依存関係を解決してロックファイルを更新
+    let lockfile = match resolve::lock_dependencies(&config, args.locked) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Err(format!("依存関係の解決に失敗: {}", e));
+        }
+    };
+
+    // 有効な feature を決め、使わない optional の依存ライブラリを外す（cpam.lock には全て記録する）
+    let enabled_features = match features::select(
+        &config,
+        &args.features.features,
+        args.features.all_features,
+        args.features.no_default_features,
+    ) {
+        Ok(enabled_features) => enabled_features,
+        Err(e) => {
+            return Err(format!("feature の指定が不正です: {}", e));
+        }
+    };
+    features::remove_disabled_dependencies(&mut config, &enabled_features);
+    if !enabled_features.is_empty() {
+        println!("有効な feature: {}", enabled_features.iter().cloned().collect::<Vec<_>>().join(", "));
+    }
+
+    let platforms = match cmake::package_platforms(&config, &lockfile) {
+        Ok(platforms) => platforms,
+        Err(e) => {
+            return Err(format!("依存関係の読み込みに失敗: {}", e));
+        }
+    };
+
+    let sources = prepare_sources(&lockfile, &platforms)?;
+
+    // vcpkg や Conan をバックエンドに使う場合はマニフェストを更新し、ツールチェーンを渡す
+    let backend_args = match config.backend() {
+        Ok(Backend::Vcpkg) => {
+            match vcpkg::write_manifest(&config, Path::new(&source_dir)).and_then(|_| vcpkg::configure_args(&config)) {
+                Ok(args) => args,
+                Err(e) => {
+                    return Err(format!("vcpkg の準備に失敗: {}", e));
+                }
+            }
+        }
+        Ok(Backend::Conan) => {
+            let installed = conan::write_conanfile(&config, Path::new(&source_dir))
+                .and_then(|_| conan::install(&config, Path::new(&source_dir), Path::new(build_dir), args.release));
+            match installed {
+                Ok(args) => args,
+                Err(e) => {
+                    return Err(format!("Conan の準備に失敗: {}", e));
+                }
+            }
+        }
+        Ok(Backend::Cpam) => Vec::new(),
+        Err(e) => {
+            return Err(e.to_string());
+        }
+    };
+
+    // 依存関係をCMakeファイルとして生成
+    if let Err(e) = cmake::write_dependencies(&config, &lockfile, &sources, &enabled_features, Path::new(build_dir)) {
+        return Err(format!("依存関係の生成に失敗: {}", e));
+    }
+
+    // CMakeLists.txt の管理ブロックを cpam.toml に合わせる
+    if config.project.is_some() {
+        if let Err(e) = cmakelists::update(&config, Path::new(&source_dir)) {
+            return Err(format!("{} の生成に失敗: {}", cmakelists::CMAKELISTS_NAME, e));
+        }
+    }
+
+    let has_dependencies = config.all_dependencies().is_ok_and(|deps| !deps.is_empty());
+    if has_dependencies && !cmake::includes_dependencies(Path::new(&source_dir)) {
+        println!("警告: CMakeLists.txt が {} を取り込んでいないため、依存ライブラリはリンクされません。", cmake::DEPS_FILE_NAME);
+        println!("add_executable の後に次の行を追加してください:");
+        println!("  {}", cmake::DEPS_INCLUDE_LINE);
+    }
+
+    let targets = args.target.clone().map(|target| vec![target]);
+    run_cmake(args, &config, &source_dir, &generator, &backend_args, targets.as_deref())
+}
+
+// ワークスペースの全メンバーを1つのビルドディレクトリと cpam.lock でビルドする（package を指定した場合はそのメンバーのみ）
+fn build_workspace(args: &BuildArgs, root: CpamConfig, package: Option<&str>) -> Result<(), String> {
+    let mut workspace = match Workspace::load(root) {
+        Ok(workspace) => workspace,
+        Err(e) => {
+            return Err(format!("ワークスペースの読み込みに失敗: {}", e));
+        }
+    };
+
+    if let Some(package) = package {
+        if workspace.find(package).is_none() {
+            let names: Vec<&str> = workspace.members.iter().map(|member| member.name()).collect();
+            return Err(format!("メンバー '{}' はワークスペースにありません（メンバー: {}）。", package, names.join(", ")));
+        }
+    }
+    if package.is_none() && !args.features.features.is_empty() {
+        return Err("ワークスペースで --features を指定する場合は -p でメンバーも指定してください。".to_string());
+    }
+
+    let build_dir = &args.build_dir;
+    if let Err(e) = fs::create_dir_all(build_dir) {
+        return Err(format!("ビルドディレクトリの作成に失敗: {}", e));
+    }
+
+    // ビルドディレクトリを共有するので、ジェネレータは -p の指定によらずルートの [build]（無ければ最初のメンバー）から決める
+    let generator = match args.generator.clone().or_else(|| workspace.root.build.as_ref().and_then(|build| build.generator.clone())) {
+        Some(generator) => generator,
+        None => workspace.members[0].config.get_cmake_generator(),
+    };
+
+    fetch::set_offline(args.offline);
+
+    // 全メンバーの依存関係をまとめて解決し、ルートの cpam.lock を更新する
+    let lockfile = match workspace.combined().and_then(|combined| resolve::lock_dependencies(&combined, args.locked)) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Err(format!("依存関係の解決に失敗: {}", e));
+        }
+    };
+
+    // feature の指定は -p のメンバーに（省略時は全てのメンバーに）使い、他のメンバーは default feature を使う
+    let mut enabled_features = BTreeMap::new();
+    for member in &mut workspace.members {
+        let name = member.name().to_string();
+        let selected = package.is_none_or(|package| package == name);
+        let enabled = if selected {
+            features::select(&member.config, &args.features.features, args.features.all_features, args.features.no_default_features)
+        } else {
+            features::select(&member.config, &[], false, false)
+        };
+        let enabled = match enabled {
+            Ok(enabled) => enabled,
+            Err(e) => {
+                return Err(format!("メンバー '{}' の feature の指定が不正です: {}", name, e));
+            }
+        };
+        features::remove_disabled_dependencies(&mut member.config, &enabled);
+        if !enabled.is_empty() {
+            println!("{}: 有効な feature: {}", name, enabled.iter().cloned().collect::<Vec<_>>().join(", "));
+        }
+        enabled_features.insert(name, enabled);
+    }
+
+    let combined = match workspace.combined() {
+        Ok(combined) => combined,
+        Err(e) => {
+            return Err(format!("依存ライブラリの読み込みに失敗: {}", e));
+        }
+    };
+    let platforms = match cmake::package_platforms(&combined, &lockfile) {
+        Ok(platforms) => platforms,
+        Err(e) => {
+            return Err(format!("依存関係の読み込みに失敗: {}", e));
+        }
+    };
+
+    let sources = prepare_sources(&lockfile, &platforms)?;
+
+    // 共有の依存ライブラリはルートのビルドディレクトリに、リンク設定は各メンバーのビルドディレクトリに生成する
+    let written = cmake::render_workspace_dependencies(&combined, &lockfile, &sources)
+        .and_then(|content| fs::write(Path::new(build_dir).join(cmake::DEPS_FILE_NAME), content));
+    if let Err(e) = written {
+        return Err(format!("依存関係の生成に失敗: {}", e));
+    }
+    for member in &workspace.members {
+        let member_build_dir = Path::new(build_dir).join(&member.dir);
+        let written = fs::create_dir_all(&member_build_dir)
+            .and_then(|_| cmake::render_member_dependencies(&member.config, &lockfile, &enabled_features[member.name()]))
+            .and_then(|content| fs::write(member_build_dir.join(cmake::DEPS_FILE_NAME), content));
+        if let Err(e) = written {
+            return Err(format!("メンバー '{}' の依存関係の生成に失敗: {}", member.name(), e));
+        }
+
+        if let Err(e) = cmakelists::update(&member.config, Path::new(&member.dir)) {
+            return Err(format!("{}/{} の生成に失敗: {}", member.dir, cmakelists::CMAKELISTS_NAME, e));
+        }
+    }
+
+    if let Err(e) = cmakelists::update_workspace(&workspace, Path::new(".")) {
+        return Err(format!("{} の生成に失敗: {}", cmakelists::CMAKELISTS_NAME, e));
+    }
+
+    // -p のメンバーは、そのメンバーの全てのターゲット（テストや examples も含む）をビルドする
+    let targets = if let Some(target) = &args.target {
+        Some(vec![target.clone()])
+    } else {
+        match package.and_then(|package| workspace.find(package)) {
+            Some(member) => match cmakelists::build_targets(&member.config, Path::new(&member.dir)) {
+                Ok(targets) => Some(targets),
+                Err(e) => {
+                    return Err(format!("メンバー '{}' のターゲットの読み込みに失敗: {}", member.name(), e));
+                }
+            },
+            None => None,
+        }
+    };
+    run_cmake(args, &workspace.root, ".", &generator, &[], targets.as_deref())
+}
+
+// 依存ライブラリのソースを用意する（vendor にコピー済みならそれを使う）
+// 無効な optional の依存ライブラリは用意せず、このホスト向けでない依存はクロスコンパイル時に FetchContent が取得する
+fn prepare_sources(lockfile: &Lockfile, platforms: &BTreeMap<String, Option<Vec<String>>>) -> Result<BTreeMap<String, PathBuf>, String> {
+    let mut sources = BTreeMap::new();
+    for package in &lockfile.packages {
+        if let Some(vendor_dir) = fetch::vendored(package) {
+            sources.insert(package.name.clone(), vendor_dir);
+            continue;
+        }
+        match platforms.get(&package.name) {
+            None => continue,
+            Some(Some(package_platforms)) if !platform::matches_host(package_platforms) => continue,
+            Some(_) => {}
+        }
+        match fetch::materialize(package) {
+            Ok(Some(source_dir)) => {
+                sources.insert(package.name.clone(), source_dir);
+            }
+            Ok(None) => {}
+            Err(e) if fetch::is_checksum_mismatch(&e) => {
+                return Err(format!(
+                    "依存ライブラリ '{}' の整合性検証に失敗: {}\n差し替えが正当な場合は cpam.toml の sha256（未指定なら cpam update {}）で記録を更新してください。",
+                    package.name, e, package.name
+                ));
+            }
+            Err(e) => {
+                return Err(format!("依存ライブラリ '{}' の取得に失敗: {}", package.name, e));
+            }
+        }
+    }
+    Ok(sources)
+}
+
+// CMake で構成してビルドする（targets を指定した場合はそのターゲットのみ）
+fn run_cmake(args: &BuildArgs, config: &CpamConfig, source_dir: &str, generator: &str, backend_args: &[String], targets: Option<&[String]>) -> Result<(), String> {
+    let build_dir = &args.build_dir;
+
     // CMakeの構成
     println!("CMakeを設定: ジェネレータ={}", generator);
     let mut cmake_config = Command::new("cmake");
-    cmake_config.args(&["-S", &source_dir, "-B", build_dir, "-G", &generator]);
+    cmake_config.args(["-S", source_dir, "-B", build_dir, "-G", generator]);
 
     // ビルドタイプを設定
     if args.release {
@@ -52,6 +321,13 @@ pub fn execute(args: &BuildArgs) {
         cmake_config.arg("-DCMAKE_BUILD_TYPE=Debug");
     }
 
+    cmake_config.args(backend_args);
+
+    // オフラインモードでは FetchContent にもネットワークを使わせない
+    if args.offline {
+        cmake_config.arg("-DFETCHCONTENT_FULLY_DISCONNECTED=ON");
+    }
+
     // 追加オプションがあれば設定
     if let Some(build) = &config.build {
         if let Some(options) = &build.options {
@@ -67,31 +343,41 @@ pub fn execute(args: &BuildArgs) {
             println!("CMakeの設定に成功しました。");
         },
         Ok(s) => {
-            eprintln!("CMake構成が失敗 (exit code: {})", s);
-            return;
+            return Err(format!("CMake構成が失敗 (exit code: {})", s));
         }
         Err(e) => {
-            eprintln!("cmake コマンドの実行に失敗: {}", e);
-            return;
+            return Err(format!("cmake コマンドの実行に失敗: {}", e));
         }
     }
 
+    // ヘッダーだけのライブラリなど、ビルドするターゲットが無い場合は構成だけで終わる
+    if targets.is_some_and(|targets| targets.is_empty()) {
+        println!("ビルドするターゲットがありません。");
+        return Ok(());
+    }
+
     // ビルド実行
     println!("ビルドを実行中...");
     let mut build_cmd = Command::new("cmake");
-    build_cmd.args(&["--build", build_dir]);
+    build_cmd.args(["--build", build_dir]);
+    if let Some(targets) = targets {
+        build_cmd.arg("--target").args(targets);
+    }
 
     if args.release {
-        build_cmd.args(&["--config", "Release"]);
+        build_cmd.args(["--config", "Release"]);
     } else {
-        build_cmd.args(&["--config", "Debug"]);
+        build_cmd.args(["--config", "Debug"]);
     }
 
     let status = build_cmd.status();
     match status {
-        Ok(s) if s.success() => println!("ビルドに成功しました。"),
-        Ok(s) => eprintln!("ビルドが失敗 (exit code: {})", s),
-        Err(e) => eprintln!("ビルドコマンドの実行に失敗: {}", e),
+        Ok(s) if s.success() => {
+            println!("ビルドに成功しました。");
+            Ok(())
+        }
+        Ok(s) => Err(format!("ビルドが失敗 (exit code: {})", s)),
+        Err(e) => Err(format!("ビルドコマンドの実行に失敗: {}", e)),
     }
 }
 
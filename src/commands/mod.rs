@@ -3,3 +3,7 @@ pub mod add;
 pub mod remove;
 pub mod build;
 pub mod run;
+pub mod tree;
+pub mod update;
+pub mod outdated;
+pub mod vendor;
@@ -1,15 +1,25 @@
 use crate::cli::NewArgs;
-use crate::config::{BuildConfig, CpamConfig, ProjectConfig};
+use crate::cmakelists;
+use crate::config::{BuildConfig, CpamConfig, ProjectConfig, LIBRARY_TYPES};
+use crate::standard;
 use std::fs;
 use std::io::{self, BufRead, Write};
 use std::path::Path;
 use std::process::Command;
 
-pub fn execute(args: &NewArgs) {
+pub fn execute(args: &NewArgs) -> Result<(), String> {
     // 完全に対話形式のプロジェクト作成プロセス
     let project_name = get_project_name(args.name.as_deref());
     let project_type = &args.project_type;
+    if project_type != "bin" && project_type != "lib" {
+        return Err(format!("エラー: プロジェクト種別 '{}' には対応していません（bin か lib を指定してください）。", project_type));
+    }
+    let library_type = match project_type.as_str() {
+        "lib" => Some(select_library_type(args.library_type.as_deref())?),
+        _ => None,
+    };
     let language = select_language(args.language.as_deref());
+    let standard = select_standard(args.standard.as_deref(), &language)?;
     let build_tool = select_build_tool(args.build_tool.as_deref());
     let compiler = select_compiler(args.compiler.as_deref(), &language);
 
@@ -17,9 +27,13 @@ pub fn execute(args: &NewArgs) {
     println!("\n===== プロジェクト設定の確認 =====");
     println!("- 名前: {}", project_name);
     println!("- 言語: {}", language);
+    println!("- 規格: {}", standard.as_deref().unwrap_or("コンパイラの既定"));
     println!("- ビルドツール: {}", build_tool);
     println!("- コンパイラ: {}", compiler);
-    println!("- 種別: {}", project_type);
+    match &library_type {
+        Some(library_type) => println!("- 種別: {} ({})", project_type, library_type),
+        None => println!("- 種別: {}", project_type),
+    }
     println!("===============================");
     print!("この設定でプロジェクトを作成しますか？ [Y/n]: ");
     io::stdout().flush().unwrap();
@@ -28,7 +42,7 @@ pub fn execute(args: &NewArgs) {
     io::stdin().lock().read_line(&mut confirm).expect("入力の読み取りに失敗しました");
     if confirm.trim().to_lowercase() == "n" {
         println!("操作をキャンセルしました。もう一度実行して設定し直してください。");
-        return;
+        return Ok(());
     }
 
     // プロジェクトのルートディレクトリを作成
@@ -42,18 +56,19 @@ pub fn execute(args: &NewArgs) {
         io::stdin().lock().read_line(&mut overwrite).expect("入力の読み取りに失敗しました");
         if !overwrite.trim().to_lowercase().starts_with('y') {
             println!("操作をキャンセルしました。");
-            return;
+            return Ok(());
         }
     } else if let Err(e) = fs::create_dir(base_path) {
-        println!("エラー: プロジェクトディレクトリの作成に失敗しました。");
-        println!("詳細: {}", e);
-        println!("別の名前でプロジェクトを作成するか、既存のディレクトリを確認してください。");
-        return;
+        return Err(format!(
+            "エラー: プロジェクトディレクトリの作成に失敗しました。\n詳細: {}\n別の名前でプロジェクトを作成するか、既存のディレクトリを確認してください。",
+            e
+        ));
     }
 
     // 標準的なプロジェクトディレクトリ構造を作成
     println!("\nプロジェクト構造を作成中...");
-    for dir in &["include", "src", "lib"] {
+    let dirs: &[&str] = if library_type.is_some() { &["include", "src", "lib", "tests"] } else { &["include", "src", "lib"] };
+    for dir in dirs {
         let path = base_path.join(dir);
         if let Err(e) = fs::create_dir_all(&path) {
             println!("警告: {} ディレクトリの作成に失敗しました: {}", dir, e);
@@ -61,63 +76,20 @@ pub fn execute(args: &NewArgs) {
         }
     }
 
-    // 言語に応じた main ファイルを作成
-    let (main_file_name, main_content) = match language.as_str() {
-        "c" => (
-            "main.c",
-            "#include <stdio.h>\n\nint main() {\n    printf(\"Hello, World!\\n\");\n    return 0;\n}\n",
-        ),
-        "cuda" => (
-            "main.cu",
-            "#include <stdio.h>\n\n__global__ void hello() {\n    printf(\"Hello, World!\\n\");\n}\n\nint main() {\n    hello<<<1,1>>>();\n    cudaDeviceSynchronize();\n    return 0;\n}\n",
-        ),
-        _ => (
-            "main.cpp",
-            "#include <iostream>\n\nint main() {\n    std::cout << \"Hello, World!\" << std::endl;\n    return 0;\n}\n",
-        ),
-    };
-
-    // src/main.xxx を作成
-    let main_file_path = base_path.join("src").join(main_file_name);
-    if let Err(e) = fs::write(&main_file_path, main_content) {
-        println!("警告: メインソースファイルの作成に失敗しました: {}", e);
-        println!("続行しますが、後でソースファイルを手動で作成する必要があります。");
-    } else {
-        println!("ソースファイル {} を作成しました", main_file_name);
-    }
-
-    // CMakeLists.txt の作成
-    let cmake_language = match language.as_str() {
-        "c" => "C",
-        "cuda" => "CUDA",
-        _ => "CXX",
+    // 種別と言語に応じたソースファイルを作成
+    let files = match &library_type {
+        Some(library_type) => library_files(&project_name, &language, library_type),
+        None => vec![main_file(&language)],
     };
-
-    // コンパイラが選択されている場合、CMakeに設定を追加
-    let compiler_config = if compiler != "default" {
-        match cmake_language {
-            "C" => format!("\nset(CMAKE_C_COMPILER {})\n", compiler),
-            "CUDA" => format!("\nset(CMAKE_CUDA_COMPILER {})\n", compiler),
-            _ => format!("\nset(CMAKE_CXX_COMPILER {})\n", compiler),
+    for (file_name, content) in files {
+        let file_path = base_path.join(&file_name);
+        let created = file_path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&file_path, content));
+        if let Err(e) = created {
+            println!("警告: ソースファイル {} の作成に失敗しました: {}", file_name, e);
+            println!("続行しますが、後でソースファイルを手動で作成する必要があります。");
+        } else {
+            println!("ソースファイル {} を作成しました", file_name);
         }
-    } else {
-        String::new()
-    };
-
-    let cmake_content = format!(
-        "cmake_minimum_required(VERSION 3.10)\n\
-         project({} LANGUAGES {})\n\
-         set(CMAKE_CXX_STANDARD 17){}\n\n\
-         add_executable({} src/{})\n",
-        project_name, cmake_language, compiler_config, project_name, main_file_name
-    );
-
-    let cmake_path = base_path.join("CMakeLists.txt");
-    if let Err(e) = fs::write(&cmake_path, cmake_content) {
-        println!("警告: CMakeLists.txt の作成に失敗しました: {}", e);
-        println!("続行しますが、後でCMakeLists.txtを手動で作成する必要があります。");
-    } else {
-        println!("CMakeLists.txt を作成しました");
     }
 
     // cpam.toml の設定ファイル作成
@@ -127,20 +99,21 @@ pub fn execute(args: &NewArgs) {
             language: language.clone(),
             build_tool: build_tool.clone(),
             project_type: project_type.clone(),
+            standard: standard.clone(),
+            library_type: library_type.clone(),
         }),
         build: Some(BuildConfig {
             generator: Some(get_generator_for_build_tool(&build_tool)),
             build_dir: Some("build".to_string()),
             source_dir: Some(".".to_string()),
             options: if compiler != "default" {
-                Some(vec![format!("-DCMAKE_{}_COMPILER={}",
-                    if cmake_language == "CXX" { "CXX" } else { cmake_language },
-                    compiler)])
+                Some(vec![format!("-DCMAKE_{}_COMPILER={}", compiler_language(&language), compiler)])
             } else {
                 None
             },
+            ..Default::default()
         }),
-        dependencies: None,
+        ..Default::default()
     };
 
     let toml_str = match toml::to_string(&config) {
@@ -155,6 +128,15 @@ pub fn execute(args: &NewArgs) {
         }
     };
 
+    // CMakeLists.txt の作成（cpam build のたびに管理ブロックを cpam.toml から再生成する）
+    match cmakelists::update(&config, base_path) {
+        Ok(()) => println!("CMakeLists.txt を作成しました"),
+        Err(e) => {
+            println!("警告: CMakeLists.txt の作成に失敗しました: {}", e);
+            println!("続行しますが、後でCMakeLists.txtを手動で作成する必要があります。");
+        }
+    }
+
     let cpam_toml_path = base_path.join("cpam.toml");
     if let Err(e) = fs::write(&cpam_toml_path, toml_str) {
         println!("警告: cpam.toml の作成に失敗しました: {}", e);
@@ -167,6 +149,131 @@ pub fn execute(args: &NewArgs) {
     println!("  cd {}", project_name);
     println!("  cpam build        # プロジェクトをビルド");
     println!("  cpam run          # プロジェクトを実行");
+    Ok(())
+}
+
+// 言語に応じた main ファイル
+fn main_file(language: &str) -> (String, String) {
+    let (file_name, content) = match language {
+        "c" => (
+            "src/main.c",
+            "#include <stdio.h>\n\nint main() {\n    printf(\"Hello, World!\\n\");\n    return 0;\n}\n",
+        ),
+        "cuda" => (
+            "src/main.cu",
+            "#include <stdio.h>\n\n__global__ void hello() {\n    printf(\"Hello, World!\\n\");\n}\n\nint main() {\n    hello<<<1,1>>>();\n    cudaDeviceSynchronize();\n    return 0;\n}\n",
+        ),
+        _ => (
+            "src/main.cpp",
+            "#include <iostream>\n\nint main() {\n    std::cout << \"Hello, World!\" << std::endl;\n    return 0;\n}\n",
+        ),
+    };
+    (file_name.to_string(), content.to_string())
+}
+
+// ライブラリの公開ヘッダー、ソース、それを使うテスト
+// ヘッダーは include/<名前>/ に置き、利用側は #include <名前/名前.h> で取り込む
+fn library_files(name: &str, language: &str, library_type: &str) -> Vec<(String, String)> {
+    let identifier = identifier(name);
+    let header_only = library_type == "interface";
+    let mut files = Vec::new();
+
+    if language == "c" {
+        let guard = format!("{}_H", identifier.to_uppercase());
+        let declaration = if header_only {
+            format!("static inline int {}_add(int a, int b) {{\n    return a + b;\n}}\n", identifier)
+        } else {
+            format!("int {}_add(int a, int b);\n", identifier)
+        };
+        files.push((
+            format!("include/{}/{}.h", name, name),
+            format!("#ifndef {}\n#define {}\n\n{}\n#endif\n", guard, guard, declaration),
+        ));
+        if !header_only {
+            files.push((
+                format!("src/{}.c", name),
+                format!("#include <{}/{}.h>\n\nint {}_add(int a, int b) {{\n    return a + b;\n}}\n", name, name, identifier),
+            ));
+        }
+        files.push((
+            format!("tests/{}_test.c", identifier),
+            format!(
+                "#include <stdio.h>\n#include <{}/{}.h>\n\nint main(void) {{\n    if ({}_add(2, 3) != 5) {{\n        printf(\"FAILED\\n\");\n        return 1;\n    }}\n    printf(\"OK\\n\");\n    return 0;\n}}\n",
+                name, name, identifier
+            ),
+        ));
+        return files;
+    }
+
+    let declaration = if header_only {
+        "inline int add(int a, int b) {\n    return a + b;\n}\n".to_string()
+    } else {
+        "int add(int a, int b);\n".to_string()
+    };
+    files.push((
+        format!("include/{}/{}.hpp", name, name),
+        format!("#pragma once\n\nnamespace {} {{\n\n{}\n}}  // namespace {}\n", identifier, declaration, identifier),
+    ));
+    if !header_only {
+        files.push((
+            format!("src/{}.{}", name, if language == "cuda" { "cu" } else { "cpp" }),
+            format!(
+                "#include <{}/{}.hpp>\n\nnamespace {} {{\n\nint add(int a, int b) {{\n    return a + b;\n}}\n\n}}  // namespace {}\n",
+                name, name, identifier, identifier
+            ),
+        ));
+    }
+    files.push((
+        format!("tests/{}_test.cpp", identifier),
+        format!(
+            "#include <iostream>\n#include <{}/{}.hpp>\n\nint main() {{\n    if ({}::add(2, 3) != 5) {{\n        std::cout << \"FAILED\" << std::endl;\n        return 1;\n    }}\n    std::cout << \"OK\" << std::endl;\n    return 0;\n}}\n",
+            name, name, identifier
+        ),
+    ));
+    files
+}
+
+// プロジェクト名を C/C++ の識別子にする（"my-lib" → "my_lib"）
+fn identifier(name: &str) -> String {
+    let identifier: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", identifier)
+    } else {
+        identifier
+    }
+}
+
+// ライブラリの種類を対話形式で選択する関数
+fn select_library_type(library_type_arg: Option<&str>) -> Result<String, String> {
+    if let Some(library_type) = library_type_arg {
+        if !LIBRARY_TYPES.contains(&library_type) {
+            return Err(format!("エラー: ライブラリの種類 '{}' には対応していません（{} のいずれかを指定してください）。", library_type, LIBRARY_TYPES.join(", ")));
+        }
+        return Ok(library_type.to_string());
+    }
+
+    println!("\nライブラリの種類を選択してください:");
+    println!("1. 静的ライブラリ (static) - 利用側の実行ファイルに組み込まれる");
+    println!("2. 共有ライブラリ (shared) - 実行時に読み込まれる .so / .dll / .dylib");
+    println!("3. ヘッダーオンリー (interface) - ヘッダーだけで提供する");
+    print!("選択 [1-3] > ");
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input).expect("入力の読み取りに失敗しました");
+    let input = input.trim();
+
+    match input {
+        "2" | "shared" => Ok("shared".to_string()),
+        "3" | "interface" => Ok("interface".to_string()),
+        "static" => Ok("static".to_string()),
+        _ => {
+            if !input.is_empty() && input != "1" {
+                println!("注意: 入力「{}」を認識できません。デフォルトの「static」を使用します。", input);
+            }
+            Ok("static".to_string())
+        }
+    }
 }
 
 // プロジェクト名を取得または生成する関数
@@ -241,6 +348,50 @@ fn select_language(language_arg: Option<&str>) -> String {
     }
 }
 
+// 言語の規格を対話形式で選択する関数（None はコンパイラの既定）
+fn select_standard(standard_arg: Option<&str>, language: &str) -> Result<Option<String>, String> {
+    if let Some(standard) = standard_arg {
+        if let Err(e) = standard::parse(language, standard) {
+            return Err(format!("エラー: {}", e));
+        }
+        return Ok(Some(standard.to_lowercase()));
+    }
+
+    let standards = standard::supported(language);
+    let default = standard::default_standard(language);
+    println!("\n言語の規格を選択してください:");
+    for (i, standard) in standards.iter().enumerate() {
+        let mark = if Some(*standard) == default { "（デフォルト）" } else { "" };
+        println!("{}. {}{}", i + 1, standard, mark);
+    }
+    if default.is_none() {
+        println!("空欄: コンパイラの既定（デフォルト）");
+    }
+    println!("コンパイラ拡張を使う場合は gnu11 や gnu++17 のように入力してください。");
+    print!("選択 [1-{}] > ", standards.len());
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input).expect("入力の読み取りに失敗しました");
+    let input = input.trim();
+
+    if input.is_empty() {
+        return Ok(default.map(|standard| standard.to_string()));
+    }
+    if let Ok(num) = input.parse::<usize>() {
+        if num >= 1 && num <= standards.len() {
+            return Ok(Some(standards[num - 1].to_string()));
+        }
+    }
+    match standard::parse(language, input) {
+        Ok(_) => Ok(Some(input.to_lowercase())),
+        Err(_) => {
+            println!("注意: 入力「{}」を認識できません。デフォルトの規格を使用します。", input);
+            Ok(default.map(|standard| standard.to_string()))
+        }
+    }
+}
+
 // ビルドツールを対話形式で選択する関数
 fn select_build_tool(build_tool_arg: Option<&str>) -> String {
     if let Some(tool) = build_tool_arg {
@@ -344,10 +495,8 @@ fn detect_available_compilers(language: &str) -> Vec<String> {
     };
 
     // Windowsの場合はMSVC系も追加
-    if cfg!(target_os = "windows") {
-        if language == "cpp" || language == "c" {
-            compilers.push("cl.exe".to_string()); // Visual C++ compiler
-        }
+    if cfg!(target_os = "windows") && (language == "cpp" || language == "c") {
+        compilers.push("cl.exe".to_string()); // Visual C++ compiler
     }
 
     // コンパイラの存在を確認
@@ -365,6 +514,15 @@ fn detect_available_compilers(language: &str) -> Vec<String> {
     compilers
 }
 
+// コンパイラを指定する CMake 変数の言語名
+fn compiler_language(language: &str) -> &'static str {
+    match language {
+        "c" => "C",
+        "cuda" => "CUDA",
+        _ => "CXX",
+    }
+}
+
 fn get_generator_for_build_tool(build_tool: &str) -> String {
     match build_tool.to_lowercase().as_str() {
         "ninja" => "Ninja".to_string(),
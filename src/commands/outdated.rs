@@ -0,0 +1,137 @@
+use crate::cli::OutdatedArgs;
+use crate::lockfile::{Lockfile, LOCKFILE_NAME};
+use crate::registry;
+use crate::resolve;
+use crate::version;
+use crate::workspace;
+use serde::Serialize;
+
+// 依存ライブラリ1件分のバージョン情報
+#[derive(Serialize)]
+struct OutdatedEntry {
+    name: String,
+    requirement: String,
+    // cpam.lock に記録されたバージョン
+    locked: Option<String>,
+    // 要求を満たす最新バージョン
+    compatible: Option<String>,
+    // 公開されている最新バージョン
+    latest: Option<String>,
+}
+
+pub fn execute(args: &OutdatedArgs) -> Result<(), String> {
+    // 設定を読み込む（ワークスペースでは全メンバーの依存関係をまとめる）
+    let config = match workspace::load_dependency_config() {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(format!("cpam.toml の読み込みに失敗: {}", e));
+        }
+    };
+
+    let lockfile = match Lockfile::load() {
+        Ok(lockfile) => lockfile.unwrap_or_default(),
+        Err(e) => {
+            return Err(format!("{} の読み込みに失敗: {}", LOCKFILE_NAME, e));
+        }
+    };
+
+    let registries = match registry::configured(&config) {
+        Ok(registries) => registries,
+        Err(e) => {
+            return Err(format!("レジストリ設定の読み込みに失敗: {}", e));
+        }
+    };
+
+    let dependencies = match config.cpam_dependencies() {
+        Ok(dependencies) => dependencies,
+        Err(e) => {
+            return Err(format!("依存ライブラリの指定が不正です: {}", e));
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (name, dependency) in dependencies {
+        let requirement = match version::parse_requirement(dependency.version()) {
+            Ok(requirement) => requirement,
+            Err(e) => {
+                return Err(format!("依存ライブラリ '{}' の指定が不正です: {}", name, e));
+            }
+        };
+
+        let versions = match resolve::available_versions(name, dependency, &registries) {
+            Ok(versions) => versions.unwrap_or_default(),
+            Err(e) => {
+                return Err(format!("依存ライブラリ '{}' のバージョン一覧の取得に失敗: {}", name, e));
+            }
+        };
+
+        // プレリリースは明示的に要求された場合のみ対象にする
+        let compatible = versions.iter().filter(|version| requirement.matches(version)).max();
+        let latest = versions.iter().filter(|version| version.pre.is_empty()).max().or(versions.iter().max());
+
+        entries.push(OutdatedEntry {
+            name: name.to_string(),
+            requirement: dependency.version().to_string(),
+            locked: lockfile.find(name).map(|package| package.version.clone()),
+            compatible: compatible.map(|version| version.to_string()),
+            latest: latest.map(|version| version.to_string()),
+        });
+    }
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&entries).map_err(|e| format!("JSON への変換に失敗: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("依存ライブラリはありません。");
+        return Ok(());
+    }
+
+    print_table(&entries);
+    Ok(())
+}
+
+fn print_table(entries: &[OutdatedEntry]) {
+    let headers = ["名前", "要求", "ロック", "互換最新", "最新"];
+    let rows: Vec<[String; 5]> = entries
+        .iter()
+        .map(|entry| {
+            let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+            [
+                entry.name.clone(),
+                entry.requirement.clone(),
+                or_dash(&entry.locked),
+                or_dash(&entry.compatible),
+                or_dash(&entry.latest),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(display_width);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    print_row(&headers.map(|header| header.to_string()), &widths);
+    for row in &rows {
+        print_row(row, &widths);
+    }
+}
+
+fn print_row(cells: &[String; 5], widths: &[usize; 5]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - display_width(cell))))
+        .collect();
+    println!("{}", line.join("  ").trim_end());
+}
+
+// 端末上の表示幅（全角文字は2桁として数える）
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
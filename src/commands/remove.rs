@@ -1,32 +1,31 @@
 use crate::cli::RemoveArgs;
-use crate::config::CpamConfig;
+use crate::config::{CpamConfig, DependencyKind};
 
-pub fn execute(args: &RemoveArgs) {
+pub fn execute(args: &RemoveArgs) -> Result<(), String> {
     // 現在の設定を読み込む
     let mut config = match CpamConfig::load() {
         Ok(config) => config,
         Err(e) => {
-            eprintln!("cpam.toml の読み込みに失敗: {}", e);
-            return;
+            return Err(format!("cpam.toml の読み込みに失敗: {}", e));
         }
     };
 
-    // 依存関係から削除
-    if let Some(dependencies) = &mut config.dependencies {
-        if dependencies.remove(&args.name).is_none() {
-            eprintln!("依存ライブラリ '{}' は設定に存在しません。", args.name);
-            return;
+    // 依存関係から削除（どのテーブルにあっても削除する）
+    let mut removed = false;
+    for kind in DependencyKind::ALL {
+        if let Some(dependencies) = config.dependency_table_mut(kind) {
+            removed |= dependencies.remove(&args.name).is_some();
         }
-    } else {
-        eprintln!("依存ライブラリ '{}' は設定に存在しません。", args.name);
-        return;
+    }
+    if !removed {
+        return Err(format!("依存ライブラリ '{}' は設定に存在しません。", args.name));
     }
 
     // 設定を保存
     if let Err(e) = config.save() {
-        eprintln!("cpam.toml の更新に失敗: {}", e);
-        return;
+        return Err(format!("cpam.toml の更新に失敗: {}", e));
     }
 
     println!("依存ライブラリ '{}' を削除しました。", args.name);
+    Ok(())
 }
@@ -1,20 +1,30 @@
 use crate::cli::RunArgs;
 use crate::commands::build::execute as execute_build;
 use crate::cli::BuildArgs;
+use crate::cmakelists::{self, TargetKind};
 use crate::config::CpamConfig;
+use crate::workspace::{self, Workspace};
 use std::path::Path;
 use std::process::Command;
-use std::io::{self, Write};
+use std::io::{self, Error, ErrorKind, Write};
+
+pub fn execute(args: &RunArgs) -> Result<(), String> {
+    // ワークスペースのメンバーのディレクトリで実行した場合は、ルートからそのメンバーを実行する
+    let member = match workspace::enter_root() {
+        Ok(member) => member,
+        Err(e) => {
+            return Err(format!("ワークスペースの読み込みに失敗: {}", e));
+        }
+    };
 
-pub fn execute(args: &RunArgs) {
     // 設定を読み込むか確認
     let config = match CpamConfig::load() {
         Ok(config) => {
-            // 念のためプロジェクト情報があるか確認
-            if config.project.is_none() {
+            // 念のためプロジェクト情報があるか確認（ワークスペースではメンバーの情報を使う）
+            if config.project.is_none() && config.workspace.is_none() {
                 println!("プロジェクト情報が見つかりません。実行する前に確認が必要です。");
                 if !confirm_continue() {
-                    return;
+                    return Ok(());
                 }
             }
             config
@@ -23,23 +33,53 @@ pub fn execute(args: &RunArgs) {
             println!("cpam.toml が見つからないか、読み込めません。");
             println!("ビルドと実行を続行しますか？");
             if !confirm_continue() {
-                return;
+                return Ok(());
             }
             CpamConfig::default()
         }
     };
 
-    // まずビルドを実行
-    let build_args = BuildArgs {
-        release: args.release,
-        build_dir: "build".to_string(),
-        generator: None,
+    // ビルドディレクトリを取得
+    let mut build_dir = if let Some(build) = &config.build {
+        build.build_dir.clone().unwrap_or_else(|| "build".to_string())
+    } else {
+        "build".to_string()
     };
-    execute_build(&build_args);
 
-    // プロジェクト名を取得
-    let project_name = if let Some(project) = &config.project {
-        project.name.clone()
+    // ワークスペースでは -p のメンバーを実行する（実行ファイルはメンバーごとのビルドディレクトリにできる）
+    let package = args.package.clone().or(member);
+    let (config, source_dir) = if config.workspace.is_some() {
+        match workspace_member(config, package.as_deref()) {
+            Ok((member_config, dir)) => {
+                build_dir = format!("{}/{}", build_dir, dir);
+                (member_config, dir)
+            }
+            Err(e) => {
+                return Err(format!("{}", e));
+            }
+        }
+    } else {
+        let source_dir = config.build.as_ref().and_then(|build| build.source_dir.clone()).unwrap_or_else(|| ".".to_string());
+        (config, source_dir)
+    };
+
+    // 実行するターゲットを決める（cpam.toml が無いか不完全な場合はビルド後に尋ねる）
+    let target_name = match &config.project {
+        Some(_) => match select_target(&config, Path::new(&source_dir), args) {
+            Ok(name) => Some(name),
+            Err(e) => {
+                return Err(format!("{}", e));
+            }
+        },
+        None => args.bin.clone().or_else(|| args.example.clone()),
+    };
+
+    // まずビルドを実行（ビルドに失敗した場合は古い実行ファイルを起動しない）
+    execute_build(&build_args(args, package, target_name.clone()))?;
+
+    // 実行ファイル名を取得
+    let project_name = if let Some(name) = target_name {
+        name
     } else {
         // cpam.tomlが無いか不完全な場合は実行ファイル名を尋ねる
         println!("実行ファイル名を入力してください:");
@@ -50,13 +90,6 @@ pub fn execute(args: &RunArgs) {
         input.trim().to_string()
     };
 
-    // ビルドディレクトリを取得
-    let build_dir = if let Some(build) = &config.build {
-        build.build_dir.clone().unwrap_or_else(|| "build".to_string())
-    } else {
-        "build".to_string()
-    };
-
     // 使用しているジェネレーターを取得
     let generator = config.get_cmake_generator();
 
@@ -71,47 +104,124 @@ pub fn execute(args: &RunArgs) {
 
         // 代替パスを試す
         let alt_paths = get_alternative_executable_paths(&build_dir, &project_name, args.release);
-        let mut executable_found = false;
 
         for alt_path in &alt_paths {
             println!("代替パスを確認中: {}", alt_path);
             if Path::new(alt_path).exists() {
                 println!("実行ファイルが見つかりました: {}", alt_path);
-                executable_found = true;
-
-                println!("実行ファイルを起動: {}", alt_path);
-                let status = Command::new(alt_path).status();
-                match status {
-                    Ok(s) if s.success() => println!("プログラムは正常に実行されました。"),
-                    Ok(s) => eprintln!("実行ファイルが異常終了 (exit code: {})", s),
-                    Err(e) => eprintln!("実行ファイルの起動に失敗: {}", e),
-                }
-
-                break;
+                return launch(alt_path);
             }
         }
 
-        if !executable_found {
-            eprintln!("実行ファイルが見つかりません。ビルドが正常に完了したか確認してください。");
-            eprintln!("検索したパス:");
-            eprintln!("- {}", executable_path);
-            for path in &alt_paths {
-                eprintln!("- {}", path);
-            }
+        let mut message = String::from("実行ファイルが見つかりません。ビルドが正常に完了したか確認してください。\n検索したパス:");
+        message.push_str(&format!("\n- {}", executable_path));
+        for path in &alt_paths {
+            message.push_str(&format!("\n- {}", path));
         }
-
-        return;
+        return Err(message);
     }
 
+    launch(&executable_path)
+}
+
+// 実行ファイルを起動する（異常終了や起動の失敗はエラーにする）
+fn launch(executable_path: &str) -> Result<(), String> {
     println!("実行ファイルを起動: {}", executable_path);
-    let status = Command::new(&executable_path).status();
-    match status {
-        Ok(s) if s.success() => println!("プログラムは正常に実行されました。"),
-        Ok(s) => eprintln!("実行ファイルが異常終了 (exit code: {})", s),
-        Err(e) => eprintln!("実行ファイルの起動に失敗: {}", e),
+    match Command::new(executable_path).status() {
+        Ok(s) if s.success() => {
+            println!("プログラムは正常に実行されました。");
+            Ok(())
+        }
+        Ok(s) => Err(format!("実行ファイルが異常終了 (exit code: {})", s)),
+        Err(e) => Err(format!("実行ファイルの起動に失敗: {}", e)),
+    }
+}
+
+// 実行する前のビルドの設定（実行ファイルが分かっている場合はそのターゲットをビルドする）
+fn build_args(args: &RunArgs, package: Option<String>, target: Option<String>) -> BuildArgs {
+    BuildArgs {
+        release: args.release,
+        build_dir: "build".to_string(),
+        generator: None,
+        locked: args.locked,
+        offline: args.offline,
+        package,
+        features: args.features.clone(),
+        target,
+    }
+}
+
+// ワークスペースで実行するメンバーの設定とディレクトリ
+fn workspace_member(root: CpamConfig, package: Option<&str>) -> io::Result<(CpamConfig, String)> {
+    let workspace = Workspace::load(root)?;
+    let names: Vec<String> = workspace.members.iter().map(|member| member.name().to_string()).collect();
+    let Some(package) = package else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("ワークスペースでは -p で実行するメンバーを指定してください（メンバー: {}）。", names.join(", ")),
+        ));
+    };
+    match workspace.members.into_iter().find(|member| member.name() == package) {
+        Some(member) => Ok((member.config, member.dir)),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("メンバー '{}' はワークスペースにありません（メンバー: {}）。", package, names.join(", ")),
+        )),
     }
 }
 
+// --bin, --example の指定から実行ファイル名を決める
+// 省略時はプロジェクト本体、ライブラリのプロジェクトでは実行ファイルが1つだけならそれを選ぶ
+fn select_target(config: &CpamConfig, source_dir: &Path, args: &RunArgs) -> io::Result<String> {
+    let Some(project) = &config.project else {
+        return Err(Error::new(ErrorKind::NotFound, "cpam.toml に [project] がありません。"));
+    };
+    let targets = cmakelists::targets(config, source_dir)?;
+    let names = |kind: TargetKind| -> Vec<&str> {
+        let mut names: Vec<&str> = targets.iter().filter(|target| target.kind == kind).map(|target| target.name.as_str()).collect();
+        if kind == TargetKind::Bin && !project.is_library() {
+            names.insert(0, &project.name);
+        }
+        names
+    };
+
+    let (kind, requested) = match (&args.bin, &args.example) {
+        (Some(bin), _) => (TargetKind::Bin, bin),
+        (None, Some(example)) => (TargetKind::Example, example),
+        (None, None) => {
+            let bins = names(TargetKind::Bin);
+            return match bins.as_slice() {
+                [name] => Ok(name.to_string()),
+                [] => Err(Error::new(
+                    ErrorKind::NotFound,
+                    "実行できるファイルがありません（ライブラリは src/bin/ か [[bin]] に実行ファイルを追加してください）。",
+                )),
+                // 実行ファイルのプロジェクトでは本体を実行する
+                [name, ..] if !project.is_library() => Ok(name.to_string()),
+                _ => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("実行ファイルが複数あります。--bin で指定してください: {}", bins.join(", ")),
+                )),
+            };
+        }
+    };
+
+    let available = names(kind);
+    if !available.contains(&requested.as_str()) {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "{} '{}' が見つかりません（{}: {}）。",
+                kind.table_name(),
+                requested,
+                kind.table_name(),
+                if available.is_empty() { "なし".to_string() } else { available.join(", ") }
+            ),
+        ));
+    }
+    Ok(requested.clone())
+}
+
 // ジェネレータとビルドタイプに基づいて実行ファイルのパスを取得
 fn get_executable_path(build_dir: &str, project_name: &str, generator: &str, is_release: bool) -> String {
     if cfg!(target_os = "windows") {
@@ -167,3 +277,30 @@ fn confirm_continue() -> bool {
     io::stdin().read_line(&mut input).expect("入力の読み取りに失敗しました");
     input.trim().to_lowercase().starts_with('y')
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use clap::Parser;
+    use std::fs;
+
+    const BIN_PROJECT: &str = "[project]\nname = \"app\"\nlanguage = \"cpp\"\nbuild_tool = \"make\"\nproject_type = \"bin\"\n";
+
+    #[test]
+    fn builds_the_selected_bin_or_example() {
+        let dir = TempDir::new("run-targets");
+        for file in ["src/main.cpp", "src/bin/tool.cpp", "examples/demo.cpp"] {
+            fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let config: CpamConfig = toml::from_str(BIN_PROJECT).unwrap();
+
+        for (flags, expected) in [(vec![], "app"), (vec!["--bin", "tool"], "tool"), (vec!["--example", "demo"], "demo")] {
+            let args = RunArgs::parse_from(["run"].into_iter().chain(flags));
+            let target = select_target(&config, &dir, &args).unwrap();
+            assert_eq!(target, expected);
+            assert_eq!(build_args(&args, None, Some(target)).target.as_deref(), Some(expected));
+        }
+    }
+}
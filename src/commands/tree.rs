@@ -0,0 +1,165 @@
+use crate::cli::TreeArgs;
+use crate::config::DependencyKind;
+use crate::lockfile::{LockedPackage, Lockfile};
+use crate::resolve;
+use crate::workspace;
+use std::collections::BTreeMap;
+
+pub fn execute(args: &TreeArgs) -> Result<(), String> {
+    // 設定を読み込む（ワークスペースでは全メンバーの依存関係をまとめる）
+    let config = match workspace::load_dependency_config() {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(format!("cpam.toml の読み込みに失敗: {}", e));
+        }
+    };
+
+    // 依存関係を解決
+    let lockfile = match resolve::lock_dependencies(&config, false) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Err(format!("依存関係の解決に失敗: {}", e));
+        }
+    };
+
+    let root = config.project.as_ref().map(|project| project.name.clone()).unwrap_or_else(|| "(root)".to_string());
+    let dependencies = match config.all_dependencies() {
+        Ok(dependencies) => dependencies,
+        Err(e) => {
+            return Err(format!("依存ライブラリの指定が不正です: {}", e));
+        }
+    };
+    let direct: Vec<String> = dependencies.iter().map(|(_, name, _)| name.to_string()).collect();
+    let kinds = dependencies.iter().map(|(kind, name, _)| (name.to_string(), *kind)).collect();
+    let graph = Graph { root, direct, kinds, lockfile };
+
+    if args.duplicates {
+        print_duplicates(&graph, args.depth);
+        return Ok(());
+    }
+
+    if let Some(name) = &args.invert {
+        if graph.lockfile.find(name).is_none() {
+            return Err(format!("依存ライブラリ '{}' は依存関係に含まれていません。", name));
+        }
+        print_tree(&graph, name, true, args.depth);
+        return Ok(());
+    }
+
+    let root = graph.root.clone();
+    print_tree(&graph, &root, false, args.depth);
+    Ok(())
+}
+
+// プロジェクトを根とする依存グラフ
+struct Graph {
+    root: String,
+    direct: Vec<String>,
+    // 直接の依存ライブラリを書いたテーブルの種類
+    kinds: BTreeMap<String, DependencyKind>,
+    lockfile: Lockfile,
+}
+
+impl Graph {
+    // 依存先（invert の場合は依存元）を返す
+    fn neighbors(&self, name: &str, invert: bool) -> Vec<String> {
+        if invert {
+            let mut parents: Vec<String> = self
+                .lockfile
+                .packages
+                .iter()
+                .filter(|package| package.dependencies.iter().flatten().any(|child| child == name))
+                .map(|package| package.name.clone())
+                .collect();
+            if self.direct.iter().any(|direct| direct == name) {
+                parents.push(self.root.clone());
+            }
+            parents.sort();
+            parents
+        } else if name == self.root {
+            self.direct.clone()
+        } else {
+            self.lockfile.find(name).and_then(|package| package.dependencies.clone()).unwrap_or_default()
+        }
+    }
+
+    fn label(&self, name: &str) -> String {
+        let mut label = match self.lockfile.find(name) {
+            Some(package) => format!("{} {} ({})", package.name, package.version, describe_source(package)),
+            None => name.to_string(),
+        };
+        if self.lockfile.find(name).is_some_and(|package| package.patched == Some(true)) {
+            label.push_str(" [patch]");
+        }
+        match self.kinds.get(name) {
+            Some(DependencyKind::Dev) => label.push_str(" [dev]"),
+            Some(DependencyKind::Build) => label.push_str(" [build]"),
+            _ => {}
+        }
+        label
+    }
+}
+
+fn describe_source(package: &LockedPackage) -> &str {
+    package.git.as_deref().or(package.url.as_deref()).or(package.path.as_deref()).unwrap_or(&package.source)
+}
+
+// 木構造で表示する（既に展開したパッケージは "(*)" を付けて省略する）
+fn print_tree(graph: &Graph, start: &str, invert: bool, depth: Option<usize>) {
+    println!("{}", graph.label(start));
+    let mut expanded = vec![start.to_string()];
+    print_children(graph, start, invert, depth, 1, "", &mut expanded);
+}
+
+fn print_children(
+    graph: &Graph,
+    name: &str,
+    invert: bool,
+    depth: Option<usize>,
+    level: usize,
+    prefix: &str,
+    expanded: &mut Vec<String>,
+) {
+    if depth.is_some_and(|depth| level > depth) {
+        return;
+    }
+
+    let children = graph.neighbors(name, invert);
+    for (index, child) in children.iter().enumerate() {
+        let last = index + 1 == children.len();
+        let branch = if last { "└── " } else { "├── " };
+        let has_children = !graph.neighbors(child, invert).is_empty();
+
+        if expanded.contains(child) && has_children {
+            println!("{}{}{} (*)", prefix, branch, graph.label(child));
+            continue;
+        }
+
+        println!("{}{}{}", prefix, branch, graph.label(child));
+        expanded.push(child.clone());
+        let next_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        print_children(graph, child, invert, depth, level + 1, &next_prefix, expanded);
+    }
+}
+
+// 複数のパッケージから要求されている依存ライブラリを、依存元の木として表示する
+fn print_duplicates(graph: &Graph, depth: Option<usize>) {
+    let duplicates: Vec<&LockedPackage> = graph
+        .lockfile
+        .packages
+        .iter()
+        .filter(|package| graph.neighbors(&package.name, true).len() > 1)
+        .collect();
+
+    if duplicates.is_empty() {
+        println!("複数のパッケージから要求されている依存ライブラリはありません。");
+        return;
+    }
+
+    for (index, package) in duplicates.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        print_tree(graph, &package.name, true, depth);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::cli::UpdateArgs;
+use crate::lockfile::{LockedPackage, Lockfile, LOCKFILE_NAME};
+use crate::resolve;
+use crate::workspace;
+
+pub fn execute(args: &UpdateArgs) -> Result<(), String> {
+    // 設定を読み込む（ワークスペースでは全メンバーの依存関係をまとめる）
+    let config = match workspace::load_dependency_config() {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(format!("cpam.toml の読み込みに失敗: {}", e));
+        }
+    };
+
+    let previous = match Lockfile::load() {
+        Ok(lockfile) => lockfile.unwrap_or_default(),
+        Err(e) => {
+            return Err(format!("{} の読み込みに失敗: {}", LOCKFILE_NAME, e));
+        }
+    };
+
+    // 指定されたパッケージだけロック内容を捨てて解決し直す
+    let reusable = match &args.name {
+        Some(name) => {
+            if previous.find(name).is_none() {
+                return Err(format!("依存ライブラリ '{}' は {} に含まれていません。", name, LOCKFILE_NAME));
+            }
+            let mut reusable = previous.clone();
+            reusable.packages.retain(|package| &package.name != name);
+            Some(reusable)
+        }
+        None => None,
+    };
+
+    let lockfile = match resolve::resolve(&config, reusable.as_ref()) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Err(format!("依存関係の解決に失敗: {}", e));
+        }
+    };
+
+    let changes = print_changes(&previous, &lockfile);
+    if args.dry_run {
+        println!("--dry-run のため {} は更新しません。", LOCKFILE_NAME);
+        return Ok(());
+    }
+
+    if changes == 0 {
+        println!("依存関係は最新です。");
+        return Ok(());
+    }
+
+    if let Err(e) = lockfile.save() {
+        return Err(format!("{} の更新に失敗: {}", LOCKFILE_NAME, e));
+    }
+    println!("{} を更新しました。", LOCKFILE_NAME);
+    Ok(())
+}
+
+// 変更内容を表示し、変更の件数を返す
+fn print_changes(previous: &Lockfile, current: &Lockfile) -> usize {
+    let mut changes = 0;
+
+    for package in &current.packages {
+        match previous.find(&package.name) {
+            None => {
+                println!("  追加: {} {}", package.name, package.version);
+                changes += 1;
+            }
+            Some(old) if old.version != package.version => {
+                println!("  更新: {} {} -> {}", package.name, old.version, package.version);
+                changes += 1;
+            }
+            Some(old) if old != package => {
+                println!("  更新: {} {} ({} -> {})", package.name, package.version, short_revision(old), short_revision(package));
+                changes += 1;
+            }
+            Some(_) => {}
+        }
+    }
+
+    for package in &previous.packages {
+        if current.find(&package.name).is_none() {
+            println!("  削除: {} {}", package.name, package.version);
+            changes += 1;
+        }
+    }
+
+    changes
+}
+
+// コミットやチェックサムの先頭部分
+fn short_revision(package: &LockedPackage) -> &str {
+    let revision = package.commit.as_deref().or(package.checksum.as_deref()).unwrap_or("-");
+    &revision[..revision.len().min(8)]
+}
@@ -0,0 +1,139 @@
+use crate::cli::VendorArgs;
+use crate::fetch::{self, VendorStamp, VENDOR_DIR, VENDOR_STAMP_NAME};
+use crate::lockfile::LockedPackage;
+use crate::resolve;
+use crate::workspace;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+pub fn execute(args: &VendorArgs) -> Result<(), String> {
+    // 設定を読み込む（ワークスペースでは全メンバーの依存関係をまとめる）
+    let config = match workspace::load_dependency_config() {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(format!("cpam.toml の読み込みに失敗: {}", e));
+        }
+    };
+
+    // 依存関係を解決
+    let lockfile = match resolve::lock_dependencies(&config, args.locked) {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Err(format!("依存関係の解決に失敗: {}", e));
+        }
+    };
+
+    let vendor_dir = Path::new(VENDOR_DIR);
+    if let Err(e) = fs::create_dir_all(vendor_dir) {
+        return Err(format!("{} ディレクトリの作成に失敗: {}", VENDOR_DIR, e));
+    }
+
+    let mut vendored = Vec::new();
+    for package in &lockfile.packages {
+        if fetch::vendored(package).is_some() {
+            vendored.push(package.name.clone());
+            continue;
+        }
+
+        let source_dir = match package_source(package) {
+            Ok(Some(source_dir)) => source_dir,
+            Ok(None) => continue,
+            Err(e) => {
+                return Err(format!("依存ライブラリ '{}' の取得に失敗: {}", package.name, e));
+            }
+        };
+
+        println!("{} {} を {} にコピー中...", package.name, package.version, VENDOR_DIR);
+        if let Err(e) = vendor_package(package, &source_dir, &vendor_dir.join(&package.name)) {
+            return Err(format!("依存ライブラリ '{}' のコピーに失敗: {}", package.name, e));
+        }
+        vendored.push(package.name.clone());
+    }
+
+    // 依存関係から外れたパッケージのコピーを削除
+    if let Err(e) = remove_stale(vendor_dir, &vendored) {
+        return Err(format!("{} の古いコピーの削除に失敗: {}", VENDOR_DIR, e));
+    }
+
+    println!("{} 個の依存ライブラリを {}/ に用意しました。", vendored.len(), VENDOR_DIR);
+    println!("cpam build は {}/ のソースを使い、ネットワークから取得しません。", VENDOR_DIR);
+    Ok(())
+}
+
+// コピー元のディレクトリ（パス依存とシステムのライブラリはコピーしないので None）
+// パス依存は手元で編集されるので、コピーすると古い内容でビルドされてしまう
+fn package_source(package: &LockedPackage) -> io::Result<Option<PathBuf>> {
+    if package.path.is_some() || package.find_package.is_some() || package.pkg_config.is_some() {
+        return Ok(None);
+    }
+
+    match fetch::materialize(package)? {
+        Some(source_dir) => Ok(Some(source_dir)),
+        None => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("'{}' に取得先が記録されていません。", package.name),
+        )),
+    }
+}
+
+fn vendor_package(package: &LockedPackage, source_dir: &Path, target: &Path) -> io::Result<()> {
+    if target.exists() {
+        fs::remove_dir_all(target)?;
+    }
+    copy_dir(source_dir, target)?;
+
+    let stamp = toml::to_string(&VendorStamp::new(package))
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{} のシリアライズに失敗: {}", VENDOR_STAMP_NAME, e)))?;
+    fs::write(target.join(VENDOR_STAMP_NAME), stamp)
+}
+
+// ディレクトリを再帰的にコピーする（.git は除く）
+fn copy_dir(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&path, &target.join(entry.file_name()))?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&path, &target.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+// シンボリックリンクを作れない環境ではリンク先の内容をコピーする
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    if source.is_dir() {
+        copy_dir(source, target)
+    } else {
+        fs::copy(source, target).map(|_| ())
+    }
+}
+
+// cpam vendor が作ったディレクトリのうち、依存関係に含まれないものを削除する
+fn remove_stale(vendor_dir: &Path, vendored: &[String]) -> io::Result<()> {
+    for entry in fs::read_dir(vendor_dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        if path.join(VENDOR_STAMP_NAME).exists() && !vendored.contains(&name) {
+            println!("{} を削除します。", path.display());
+            fs::remove_dir_all(&path)?;
+        }
+    }
+    Ok(())
+}
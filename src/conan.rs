@@ -0,0 +1,188 @@
+use crate::cmake::{self, KnownPackage};
+use crate::config::{CpamConfig, Dependency, DependencyKind};
+use crate::version;
+use semver::Op;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+use std::process::Command;
+
+// プロジェクトのソースディレクトリに生成するファイル名
+pub const CONANFILE_NAME: &str = "conanfile.txt";
+
+// CMakeToolchain ジェネレーターが出力するツールチェーンファイル名
+const TOOLCHAIN_FILE_NAME: &str = "conan_toolchain.cmake";
+
+// よく使われるパッケージの find_package 名とリンクするターゲット（CMakeDeps の出力に合わせる）
+const KNOWN_PACKAGES: &[KnownPackage] = &[
+    ("catch2", "Catch2", &["Catch2::Catch2WithMain"]),
+    ("eigen", "Eigen3", &["Eigen3::Eigen"]),
+    ("fmt", "fmt", &["fmt::fmt"]),
+    ("glfw", "glfw3", &["glfw"]),
+    ("gtest", "GTest", &["GTest::gtest", "GTest::gtest_main"]),
+    ("libcurl", "CURL", &["CURL::libcurl"]),
+    ("libpng", "PNG", &["PNG::PNG"]),
+    ("nlohmann_json", "nlohmann_json", &["nlohmann_json::nlohmann_json"]),
+    ("openssl", "OpenSSL", &["OpenSSL::SSL", "OpenSSL::Crypto"]),
+    ("spdlog", "spdlog", &["spdlog::spdlog"]),
+    ("sqlite3", "SQLite3", &["SQLite::SQLite3"]),
+    ("zlib", "ZLIB", &["ZLIB::ZLIB"]),
+];
+
+// パッケージの find_package 名とリンクするターゲット
+pub fn package_targets(package: &str, dependency: &Dependency) -> (String, Vec<String>) {
+    cmake::backend_targets(KNOWN_PACKAGES, package, dependency)
+}
+
+// Conan の参照（"zlib/1.3.1" や "fmt/[^10.2]"）
+fn reference(package: &str, requirement: &str) -> io::Result<String> {
+    let requirement = version::parse_requirement(requirement)?;
+
+    // 完全に固定されたバージョンはそのまま指定する
+    if let [comparator] = requirement.comparators.as_slice() {
+        if comparator.op == Op::Exact && comparator.patch.is_some() {
+            return Ok(format!("{}/{}", package, version::comparator_version(comparator)));
+        }
+    }
+
+    let mut ranges = Vec::new();
+    for comparator in &requirement.comparators {
+        // "=1.2" は 1.2.x のどれでもよい（Conan の "=1.2" は 1.2 そのものしか一致しない）
+        if comparator.op == Op::Exact && comparator.patch.is_none() {
+            let upper = match comparator.minor {
+                Some(minor) => format!("{}.{}", comparator.major, minor + 1),
+                None => (comparator.major + 1).to_string(),
+            };
+            ranges.push(format!(">={}", version::comparator_version(comparator)));
+            ranges.push(format!("<{}", upper));
+            continue;
+        }
+
+        let op = match comparator.op {
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+            Op::Tilde | Op::Wildcard => "~",
+            Op::Caret => "^",
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'{}' のバージョン要求を Conan の範囲指定に変換できません。", package),
+                ))
+            }
+        };
+        ranges.push(format!("{}{}", op, version::comparator_version(comparator)));
+    }
+
+    if ranges.is_empty() {
+        return Ok(format!("{}/[*]", package));
+    }
+    Ok(format!("{}/[{}]", package, ranges.join(" ")))
+}
+
+// cpam.toml の依存関係から conanfile.txt を生成する
+pub fn write_conanfile(config: &CpamConfig, source_dir: &Path) -> io::Result<()> {
+    let mut content = String::from("# このファイルは cpam build によって cpam.toml から自動生成されます。手動で編集しないでください。\n");
+
+    // dev-dependencies は test_requires、build-dependencies は tool_requires に書く
+    let dependencies = config.backend_dependencies()?;
+    // conanfile.txt には条件を書けないので、プラットフォームごとの依存は conanfile.py かプロファイルで扱う
+    if let Some((_, package, _)) = dependencies.iter().find(|(_, _, dependency)| dependency.platforms().is_some()) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Conan バックエンドの依存 '{}' には platforms を指定できません。", package),
+        ));
+    }
+    for (kind, section) in [
+        (DependencyKind::Normal, "requires"),
+        (DependencyKind::Dev, "test_requires"),
+        (DependencyKind::Build, "tool_requires"),
+    ] {
+        let references = dependencies
+            .iter()
+            .filter(|(dependency_kind, _, _)| *dependency_kind == kind)
+            .map(|(_, package, dependency)| reference(package, dependency.version()))
+            .collect::<io::Result<Vec<String>>>()?;
+        if kind != DependencyKind::Normal && references.is_empty() {
+            continue;
+        }
+
+        content.push_str(&format!("[{}]\n", section));
+        for reference in references {
+            content.push_str(&reference);
+            content.push('\n');
+        }
+        content.push('\n');
+    }
+    content.push_str("[generators]\nCMakeDeps\nCMakeToolchain\n");
+
+    let conanfile_path = source_dir.join(CONANFILE_NAME);
+    if fs::read_to_string(&conanfile_path).ok().as_deref() != Some(content.as_str()) {
+        fs::write(&conanfile_path, content)?;
+        println!("{} を更新しました。", CONANFILE_NAME);
+    }
+    Ok(())
+}
+
+// conan install でビルドディレクトリへ依存ライブラリとツールチェーンを用意し、cmake に渡す引数を返す
+pub fn install(config: &CpamConfig, source_dir: &Path, build_dir: &Path, release: bool) -> io::Result<Vec<String>> {
+    let build_type = if release { "Release" } else { "Debug" };
+    let conan = config.conan.as_ref();
+
+    let mut command = Command::new("conan");
+    command
+        .arg("install")
+        .arg(source_dir)
+        .arg("--output-folder")
+        .arg(build_dir)
+        .arg(format!("--build={}", conan.and_then(|conan| conan.build.as_deref()).unwrap_or("missing")))
+        .args(["-s", &format!("build_type={}", build_type)]);
+    if let Some(profile) = conan.and_then(|conan| conan.profile.as_deref()) {
+        command.args(["--profile", profile]);
+    }
+
+    println!("conan install を実行中...");
+    let status = command.status().map_err(|e| Error::new(
+        e.kind(),
+        format!("conan コマンドを実行できません（Conan 2 がインストールされているか確認してください）: {}", e),
+    ))?;
+    if !status.success() {
+        return Err(Error::other(format!("conan install が失敗しました ({})", status)));
+    }
+
+    let toolchain = std::path::absolute(build_dir.join(TOOLCHAIN_FILE_NAME))?;
+    if !toolchain.exists() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Conan のツールチェーンファイル '{}' が生成されませんでした。", toolchain.display()),
+        ));
+    }
+    Ok(vec![format!("-DCMAKE_TOOLCHAIN_FILE={}", toolchain.to_string_lossy().replace('\\', "/"))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_fully_specified_versions() {
+        assert_eq!(reference("zlib", "=1.3.1").unwrap(), "zlib/1.3.1");
+    }
+
+    #[test]
+    fn expands_partial_exact_versions_to_ranges() {
+        assert_eq!(reference("fmt", "=1.2").unwrap(), "fmt/[>=1.2 <1.3]");
+        assert_eq!(reference("fmt", "=1").unwrap(), "fmt/[>=1 <2]");
+        assert_eq!(reference("fmt", "=1.2, <1.2.5").unwrap(), "fmt/[>=1.2 <1.3 <1.2.5]");
+    }
+
+    #[test]
+    fn converts_ranges() {
+        assert_eq!(reference("fmt", "^10.2").unwrap(), "fmt/[^10.2]");
+        assert_eq!(reference("fmt", "~1.2.3").unwrap(), "fmt/[~1.2.3]");
+        assert_eq!(reference("fmt", ">=1.0, <2.0").unwrap(), "fmt/[>=1.0 <2.0]");
+        assert_eq!(reference("fmt", "*").unwrap(), "fmt/[*]");
+    }
+}
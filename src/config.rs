@@ -1,13 +1,137 @@
+use crate::platform;
+use crate::standard::{self, LanguageStandard};
+use crate::version;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::io::{self, Error, ErrorKind};
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CpamConfig {
     pub project: Option<ProjectConfig>,
     pub build: Option<BuildConfig>,
-    pub dependencies: Option<std::collections::HashMap<String, String>>,
+    pub dependencies: Option<BTreeMap<String, Dependency>>,
+    // テストやベンチマークだけで使う依存ライブラリ
+    #[serde(rename = "dev-dependencies")]
+    pub dev_dependencies: Option<BTreeMap<String, Dependency>>,
+    // ビルド時にホストで使うツール（protoc, flex など）
+    #[serde(rename = "build-dependencies")]
+    pub build_dependencies: Option<BTreeMap<String, Dependency>>,
+    // optional な依存ライブラリとコンパイル時の定義をまとめて有効にする feature
+    pub features: Option<BTreeMap<String, Vec<String>>>,
+    // 依存ライブラリ（推移的なものを含む）の取得元を一時的に差し替える
+    pub patch: Option<BTreeMap<String, Dependency>>,
+    pub registries: Option<BTreeMap<String, String>>,
+    // プロジェクト本体の他に作る実行ファイル（src/bin/, examples/, benches/ からも自動で見つける）
+    #[serde(rename = "bin")]
+    pub bins: Option<Vec<TargetConfig>>,
+    #[serde(rename = "example")]
+    pub examples: Option<Vec<TargetConfig>>,
+    #[serde(rename = "bench")]
+    pub benches: Option<Vec<TargetConfig>>,
+    pub vcpkg: Option<VcpkgConfig>,
+    pub conan: Option<ConanConfig>,
+    // 複数のパッケージをまとめてビルドするワークスペース（ルートの cpam.toml のみ）
+    pub workspace: Option<WorkspaceConfig>,
+    // ワークスペースのメンバーとして読み込んだか（CMake のターゲット名をメンバーごとに分ける）
+    #[serde(skip)]
+    pub workspace_member: bool,
+}
+
+// ユーザー全体で共有する設定（~/.config/cpam/config.toml など）
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UserConfig {
+    pub registries: Option<BTreeMap<String, String>>,
+}
+
+// 依存ライブラリを書くテーブルの種類
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DependencyKind {
+    // プロジェクトのターゲットにリンクする
+    Normal,
+    // テストやベンチマークにだけリンクする
+    Dev,
+    // リンクせず、ビルド時のツールとして使う
+    Build,
+}
+
+impl DependencyKind {
+    pub const ALL: [DependencyKind; 3] = [DependencyKind::Normal, DependencyKind::Dev, DependencyKind::Build];
+
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Dev => "dev-dependencies",
+            DependencyKind::Build => "build-dependencies",
+        }
+    }
+}
+
+// 依存ライブラリの指定
+// `name = "1.0"` の簡易形式と `name = { git = "...", tag = "..." }` などの詳細形式を受け付ける
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Dependency {
+    Version(String),
+    Detailed(Box<DependencyDetail>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DependencyDetail {
+    pub version: Option<String>,
+    pub git: Option<String>,
+    pub tag: Option<String>,
+    pub branch: Option<String>,
+    pub rev: Option<String>,
+    pub path: Option<String>,
+    pub url: Option<String>,
+    pub sha256: Option<String>,
+    pub registry: Option<String>,
+    // システムにインストール済みのライブラリを使う
+    pub system: Option<bool>,
+    pub find_package: Option<String>,
+    pub pkg_config: Option<String>,
+    // リンクするCMakeターゲット（省略時はパッケージ名）
+    #[serde(alias = "link")]
+    pub targets: Option<Vec<String>>,
+    // 依存ライブラリを取り込む間だけ設定する CMake の変数（BUILD_TESTING = false など）
+    pub cmake_options: Option<BTreeMap<String, toml::Value>>,
+    // 有効にするプラットフォーム（"linux", "windows+msvc" など、省略時は全て）
+    pub platforms: Option<Vec<String>>,
+    // [features] で有効にした場合のみ使う
+    pub optional: Option<bool>,
+}
+
+// 依存ライブラリの取得元
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencySource<'a> {
+    Git { url: &'a str, reference: GitReference<'a> },
+    Path(&'a str),
+    Archive { url: &'a str, sha256: Option<&'a str> },
+    // レジストリのインデックスから探す（レジストリ名の指定が無ければ全て探す）
+    Registry(Option<&'a str>),
+    // ソースを取得せず、システムにインストール済みのものを探す
+    System(SystemPackage<'a>),
+}
+
+// システムのライブラリの探し方
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemPackage<'a> {
+    // CMake の find_package に渡すパッケージ名
+    FindPackage(&'a str),
+    // pkg-config のモジュール名
+    PkgConfig(&'a str),
+}
+
+// Git リポジトリのどの版を取得するか
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitReference<'a> {
+    Tag(&'a str),
+    Branch(&'a str),
+    Rev(&'a str),
+    // 明示的な指定が無い場合はバージョン要求を満たす最新のタグを選ぶ
+    Requirement(&'a str),
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -16,6 +140,56 @@ pub struct ProjectConfig {
     pub language: String,
     pub build_tool: String,
     pub project_type: String,
+    // 言語の規格（"c11", "c++17", "gnu++20" など）
+    pub standard: Option<String>,
+    // ライブラリの種類（"static", "shared", "interface"、project_type が "lib" の場合のみ）
+    pub library_type: Option<String>,
+}
+
+// [[bin]], [[example]], [[bench]] の実行ファイル
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TargetConfig {
+    pub name: String,
+    // ソースファイルかディレクトリ（省略時は src/bin/<名前>.c などを探す）
+    pub path: Option<String>,
+}
+
+// [workspace] の設定
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct WorkspaceConfig {
+    // メンバーのディレクトリ（"libs/*" のように "*" と "?" を使える）
+    pub members: Vec<String>,
+    // members に一致してもメンバーにしないディレクトリ
+    pub exclude: Option<Vec<String>>,
+}
+
+// ライブラリとして作れる種類
+pub const LIBRARY_TYPES: &[&str] = &["static", "shared", "interface"];
+
+impl ProjectConfig {
+    pub fn is_library(&self) -> bool {
+        self.project_type == "lib"
+    }
+
+    // 言語の規格（省略時は言語ごとの既定、C は指定しない）
+    pub fn standard(&self) -> io::Result<Option<LanguageStandard>> {
+        match self.standard.as_deref().or(standard::default_standard(&self.language)) {
+            Some(name) => standard::parse(&self.language, name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // ライブラリの種類（省略時は static）
+    pub fn library_type(&self) -> io::Result<&str> {
+        match self.library_type.as_deref() {
+            None => Ok("static"),
+            Some(library_type) if LIBRARY_TYPES.contains(&library_type) => Ok(library_type),
+            Some(library_type) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("library_type '{}' には対応していません（{} のいずれかを指定してください）。", library_type, LIBRARY_TYPES.join(", ")),
+            )),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -24,6 +198,229 @@ pub struct BuildConfig {
     pub source_dir: Option<String>,
     pub build_dir: Option<String>,
     pub options: Option<Vec<String>>,
+    // 依存ライブラリの取得に使うパッケージマネージャー（"cpam"（省略時）, "vcpkg", "conan"）
+    pub backend: Option<String>,
+}
+
+// vcpkg をバックエンドに使う場合の設定
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct VcpkgConfig {
+    // vcpkg のディレクトリ（省略時は環境変数 VCPKG_ROOT）
+    pub root: Option<String>,
+    // vcpkg.json の builtin-baseline（バージョンの下限を指定するには必要）
+    pub baseline: Option<String>,
+    pub overlay_ports: Option<Vec<String>>,
+}
+
+// Conan をバックエンドに使う場合の設定
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ConanConfig {
+    // conan install に渡すプロファイル（省略時は default）
+    pub profile: Option<String>,
+    // conan install の --build に渡すポリシー（省略時は "missing"）
+    pub build: Option<String>,
+}
+
+// 依存ライブラリの取得を任せるパッケージマネージャー
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Cpam,
+    Vcpkg,
+    Conan,
+}
+
+impl DependencySource<'_> {
+    // ロックファイルで取得元を識別するための文字列
+    pub fn id(&self) -> String {
+        match self {
+            DependencySource::Git { url, reference } => match reference {
+                GitReference::Tag(tag) => format!("git+{}?tag={}", url, tag),
+                GitReference::Branch(branch) => format!("git+{}?branch={}", url, branch),
+                GitReference::Rev(rev) => format!("git+{}?rev={}", url, rev),
+                GitReference::Requirement(_) => format!("git+{}", url),
+            },
+            DependencySource::Path(path) => format!("path+{}", path),
+            DependencySource::Archive { url, .. } => format!("url+{}", url),
+            DependencySource::Registry(Some(registry)) => format!("registry+{}", registry),
+            DependencySource::Registry(None) => "registry".to_string(),
+            DependencySource::System(SystemPackage::FindPackage(package)) => format!("system+find_package={}", package),
+            DependencySource::System(SystemPackage::PkgConfig(module)) => format!("system+pkg_config={}", module),
+        }
+    }
+}
+
+impl Dependency {
+    pub fn version(&self) -> &str {
+        match self {
+            Dependency::Version(version) => version,
+            Dependency::Detailed(detail) => detail.version.as_deref().unwrap_or("*"),
+        }
+    }
+
+    // cpam.toml で指定されたリンク対象のCMakeターゲット
+    pub fn targets(&self) -> Option<&[String]> {
+        match self {
+            Dependency::Version(_) => None,
+            Dependency::Detailed(detail) => detail.targets.as_deref(),
+        }
+    }
+
+    // 依存ライブラリを有効にするプラットフォーム（None は全て）
+    pub fn platforms(&self) -> Option<&[String]> {
+        match self {
+            Dependency::Version(_) => None,
+            Dependency::Detailed(detail) => detail.platforms.as_deref(),
+        }
+    }
+
+    // cmake_options を CMake の変数名と値の組にする（真偽値は ON/OFF、配列は ";" 区切り）
+    pub fn cmake_options(&self) -> io::Result<Vec<(&str, String)>> {
+        let options = match self {
+            Dependency::Detailed(detail) => match &detail.cmake_options {
+                Some(options) => options,
+                None => return Ok(Vec::new()),
+            },
+            Dependency::Version(_) => return Ok(Vec::new()),
+        };
+
+        let mut values = Vec::new();
+        for (name, value) in options {
+            let value = match value {
+                toml::Value::Array(items) => {
+                    items.iter().map(|item| cmake_option_value(name, item)).collect::<io::Result<Vec<_>>>()?.join(";")
+                }
+                value => cmake_option_value(name, value)?,
+            };
+            values.push((name.as_str(), value));
+        }
+        Ok(values)
+    }
+
+    // [features] で有効にした場合のみ使う依存ライブラリか
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Dependency::Version(_) => false,
+            Dependency::Detailed(detail) => detail.optional == Some(true),
+        }
+    }
+
+    // 取得元を検証して返す（git, path, url, system の指定が無ければレジストリから探す）
+    pub fn source(&self) -> io::Result<DependencySource<'_>> {
+        version::parse_requirement(self.version())?;
+        if let Some(platforms) = self.platforms() {
+            platform::validate(platforms)?;
+        }
+
+        let detail = match self {
+            Dependency::Version(_) => return Ok(DependencySource::Registry(None)),
+            Dependency::Detailed(detail) => detail,
+        };
+
+        let system = detail.system == Some(true) || detail.find_package.is_some() || detail.pkg_config.is_some();
+        let kinds = [detail.git.is_some(), detail.path.is_some(), detail.url.is_some(), detail.registry.is_some(), system];
+        if kinds.iter().filter(|&&kind| kind).count() > 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "git, path, url, registry, system は同時に指定できません。"));
+        }
+        if detail.find_package.is_some() && detail.pkg_config.is_some() {
+            return Err(Error::new(ErrorKind::InvalidData, "find_package と pkg_config は同時に指定できません。"));
+        }
+        if detail.system == Some(false) && system {
+            return Err(Error::new(ErrorKind::InvalidData, "find_package, pkg_config は system = false と一緒に指定できません。"));
+        }
+
+        let references = [&detail.tag, &detail.branch, &detail.rev];
+        if references.iter().filter(|reference| reference.is_some()).count() > 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "tag, branch, rev は同時に指定できません。"));
+        }
+        if detail.git.is_none() && references.iter().any(|reference| reference.is_some()) {
+            return Err(Error::new(ErrorKind::InvalidData, "tag, branch, rev は git と一緒に指定してください。"));
+        }
+        if detail.url.is_none() && detail.sha256.is_some() {
+            return Err(Error::new(ErrorKind::InvalidData, "sha256 は url と一緒に指定してください。"));
+        }
+        if let Some(sha256) = &detail.sha256 {
+            validate_sha256(sha256)?;
+        }
+
+        if let Some(url) = &detail.git {
+            let reference = if let Some(tag) = &detail.tag {
+                GitReference::Tag(tag)
+            } else if let Some(branch) = &detail.branch {
+                GitReference::Branch(branch)
+            } else if let Some(rev) = &detail.rev {
+                GitReference::Rev(rev)
+            } else {
+                GitReference::Requirement(self.version())
+            };
+            return Ok(DependencySource::Git { url, reference });
+        }
+
+        if let Some(path) = &detail.path {
+            return Ok(DependencySource::Path(path));
+        }
+
+        if let Some(url) = &detail.url {
+            return Ok(DependencySource::Archive { url, sha256: detail.sha256.as_deref() });
+        }
+
+        if let Some(module) = &detail.pkg_config {
+            return Ok(DependencySource::System(SystemPackage::PkgConfig(module)));
+        }
+        if let Some(package) = &detail.find_package {
+            return Ok(DependencySource::System(SystemPackage::FindPackage(package)));
+        }
+        if system {
+            return Err(Error::new(ErrorKind::InvalidData, "system = true の場合は find_package か pkg_config を指定してください。"));
+        }
+
+        Ok(DependencySource::Registry(detail.registry.as_deref()))
+    }
+}
+
+fn cmake_option_value(name: &str, value: &toml::Value) -> io::Result<String> {
+    match value {
+        toml::Value::Boolean(true) => Ok("ON".to_string()),
+        toml::Value::Boolean(false) => Ok("OFF".to_string()),
+        toml::Value::String(value) => Ok(value.clone()),
+        toml::Value::Integer(value) => Ok(value.to_string()),
+        toml::Value::Float(value) => Ok(value.to_string()),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("cmake_options の '{}' には文字列、真偽値、数値かその配列を指定してください。", name),
+        )),
+    }
+}
+
+// sha256 が64桁の16進数か確認する
+pub fn validate_sha256(sha256: &str) -> io::Result<()> {
+    if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("sha256 '{}' は64桁の16進数で指定してください。", sha256),
+        ));
+    }
+    Ok(())
+}
+
+impl UserConfig {
+    // ユーザー設定ファイルのパス
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("cpam").join("config.toml"))
+    }
+
+    // ユーザー設定を読み込む（存在しない場合は既定値）
+    pub fn load() -> io::Result<Self> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(UserConfig::default()),
+        };
+
+        let toml_str = fs::read_to_string(&path)?;
+        toml::from_str(&toml_str).map_err(|e| Error::new(
+            ErrorKind::InvalidData,
+            format!("{} の解析に失敗: {}", path.display(), e),
+        ))
+    }
 }
 
 impl CpamConfig {
@@ -33,10 +430,15 @@ impl CpamConfig {
             return Err(Error::new(ErrorKind::NotFound, "cpam.toml がカレントディレクトリに存在しません。"));
         }
 
+        Self::load_from(toml_path)
+    }
+
+    // 指定した場所の cpam.toml を読み込む（ワークスペースのメンバーなど）
+    pub fn load_from(toml_path: &Path) -> io::Result<Self> {
         let toml_str = fs::read_to_string(toml_path)?;
         match toml::from_str(&toml_str) {
             Ok(config) => Ok(config),
-            Err(e) => Err(Error::new(ErrorKind::InvalidData, format!("cpam.toml の解析に失敗: {}", e))),
+            Err(e) => Err(Error::new(ErrorKind::InvalidData, format!("{} の解析に失敗: {}", toml_path.display(), e))),
         }
     }
 
@@ -47,6 +449,102 @@ impl CpamConfig {
         fs::write("cpam.toml", toml_str)
     }
 
+    pub fn backend(&self) -> io::Result<Backend> {
+        match self.build.as_ref().and_then(|build| build.backend.as_deref()) {
+            None | Some("cpam") => Ok(Backend::Cpam),
+            Some("vcpkg") => Ok(Backend::Vcpkg),
+            Some("conan") => Ok(Backend::Conan),
+            Some(backend) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("バックエンド '{}' には対応していません（cpam, vcpkg, conan のいずれかを指定してください）。", backend),
+            )),
+        }
+    }
+
+    // バックエンドに任せる依存ライブラリか（取得元の指定が無いものはバックエンドのパッケージとして扱う）
+    // [patch] で差し替えた依存ライブラリは cpam が取得する
+    pub fn is_backend_dependency(&self, name: &str, dependency: &Dependency) -> io::Result<bool> {
+        if self.patch.as_ref().is_some_and(|patch| patch.contains_key(name)) {
+            return Ok(false);
+        }
+        Ok(self.backend()? != Backend::Cpam && dependency.source()? == DependencySource::Registry(None))
+    }
+
+    // [patch] に指定があれば差し替えた依存関係を返す
+    // バージョン指定を省略した場合は元の要求を引き継ぐ
+    pub fn patched(&self, name: &str, dependency: &Dependency) -> Option<Dependency> {
+        let mut patched = self.patch.as_ref()?.get(name)?.clone();
+        if let Dependency::Detailed(detail) = &mut patched {
+            if detail.version.is_none() {
+                detail.version = Some(dependency.version().to_string());
+            }
+        }
+        Some(patched)
+    }
+
+    pub fn dependency_table(&self, kind: DependencyKind) -> Option<&BTreeMap<String, Dependency>> {
+        match kind {
+            DependencyKind::Normal => self.dependencies.as_ref(),
+            DependencyKind::Dev => self.dev_dependencies.as_ref(),
+            DependencyKind::Build => self.build_dependencies.as_ref(),
+        }
+    }
+
+    pub fn dependency_table_mut(&mut self, kind: DependencyKind) -> &mut Option<BTreeMap<String, Dependency>> {
+        match kind {
+            DependencyKind::Normal => &mut self.dependencies,
+            DependencyKind::Dev => &mut self.dev_dependencies,
+            DependencyKind::Build => &mut self.build_dependencies,
+        }
+    }
+
+    // 全てのテーブルの依存ライブラリ（同じ名前は1つのテーブルにしか書けない）
+    pub fn all_dependencies(&self) -> io::Result<Vec<(DependencyKind, &str, &Dependency)>> {
+        let mut dependencies: Vec<(DependencyKind, &str, &Dependency)> = Vec::new();
+        for kind in DependencyKind::ALL {
+            for (name, dependency) in self.dependency_table(kind).into_iter().flatten() {
+                if let Some((other, _, _)) = dependencies.iter().find(|(_, other, _)| other == name) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("'{}' が [{}] と [{}] の両方に指定されています。", name, other.table_name(), kind.table_name()),
+                    ));
+                }
+                dependencies.push((kind, name, dependency));
+            }
+        }
+        Ok(dependencies)
+    }
+
+    // cpam 自身が取得する依存ライブラリ（全てのテーブルを合わせたもの）
+    pub fn cpam_dependencies(&self) -> io::Result<BTreeMap<&str, &Dependency>> {
+        let mut dependencies = BTreeMap::new();
+        for (_, name, dependency) in self.all_dependencies()? {
+            if !self.is_backend_dependency(name, dependency)? {
+                dependencies.insert(name, dependency);
+            }
+        }
+        Ok(dependencies)
+    }
+
+    // vcpkg や Conan などのバックエンドに任せる依存ライブラリ
+    pub fn backend_dependencies(&self) -> io::Result<Vec<(DependencyKind, &str, &Dependency)>> {
+        let mut dependencies = Vec::new();
+        for (kind, name, dependency) in self.all_dependencies()? {
+            if self.is_backend_dependency(name, dependency)? {
+                dependencies.push((kind, name, dependency));
+            }
+        }
+        Ok(dependencies)
+    }
+
+    // CMake の変数名に使うプロジェクトの言語（C, CXX）
+    pub fn cmake_language(&self) -> &'static str {
+        match self.project.as_ref().map(|project| project.language.as_str()) {
+            Some("c") => "C",
+            _ => "CXX",
+        }
+    }
+
     pub fn get_cmake_generator(&self) -> String {
         if let Some(build) = &self.build {
             if let Some(generator) = &build.generator {
@@ -0,0 +1,245 @@
+use crate::config::{CpamConfig, DependencyKind};
+use std::collections::BTreeSet;
+use std::io::{self, Error, ErrorKind};
+
+// 省略時に有効にする feature の名前
+pub const DEFAULT_FEATURE: &str = "default";
+
+// [features] の各 feature に書ける項目
+// "dep:<名前>" は optional な依存ライブラリ、"define:<名前>[=<値>]" はコンパイル時の定義、それ以外は他の feature
+enum FeatureEntry<'a> {
+    Dependency(&'a str),
+    Definition(&'a str),
+    Feature(&'a str),
+}
+
+impl<'a> FeatureEntry<'a> {
+    fn parse(entry: &'a str) -> Self {
+        if let Some(dependency) = entry.strip_prefix("dep:") {
+            FeatureEntry::Dependency(dependency)
+        } else if let Some(definition) = entry.strip_prefix("define:") {
+            FeatureEntry::Definition(definition)
+        } else {
+            FeatureEntry::Feature(entry)
+        }
+    }
+}
+
+// [features] の内容を確認する
+pub fn validate(config: &CpamConfig) -> io::Result<()> {
+    let dependencies = config.all_dependencies()?;
+    for (feature, entries) in config.features.iter().flatten() {
+        for entry in entries {
+            match FeatureEntry::parse(entry) {
+                FeatureEntry::Dependency(name) => {
+                    match dependencies.iter().find(|(_, other, _)| *other == name) {
+                        Some((_, _, dependency)) if dependency.is_optional() => {}
+                        Some(_) => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                format!("feature '{}' の '{}' は optional = true の依存ライブラリではありません。", feature, name),
+                            ))
+                        }
+                        None => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                format!("feature '{}' の依存ライブラリ '{}' は cpam.toml にありません。", feature, name),
+                            ))
+                        }
+                    }
+                }
+                FeatureEntry::Definition(definition) => {
+                    let name = definition.split('=').next().unwrap_or_default();
+                    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("feature '{}' の定義 '{}' が不正です（\"define:NAME\" か \"define:NAME=VALUE\"）。", feature, definition),
+                        ));
+                    }
+                }
+                FeatureEntry::Feature(name) => {
+                    if !has_feature(config, name) {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("feature '{}' が含む feature '{}' は [features] にありません。", feature, name),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn has_feature(config: &CpamConfig, name: &str) -> bool {
+    config.features.as_ref().is_some_and(|features| features.contains_key(name))
+}
+
+// 有効にする feature を決める（指定された feature が含む feature も有効にする）
+pub fn select(config: &CpamConfig, requested: &[String], all_features: bool, no_default_features: bool) -> io::Result<BTreeSet<String>> {
+    validate(config)?;
+
+    let mut pending: Vec<String> = Vec::new();
+    if all_features {
+        pending.extend(config.features.iter().flatten().map(|(name, _)| name.clone()));
+    }
+    if !no_default_features && has_feature(config, DEFAULT_FEATURE) {
+        pending.push(DEFAULT_FEATURE.to_string());
+    }
+    for feature in requested.iter().map(|feature| feature.trim()).filter(|feature| !feature.is_empty()) {
+        if !has_feature(config, feature) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("feature '{}' は [features] にありません。", feature)));
+        }
+        pending.push(feature.to_string());
+    }
+
+    let mut enabled = BTreeSet::new();
+    while let Some(feature) = pending.pop() {
+        if !enabled.insert(feature.clone()) {
+            continue;
+        }
+        for entry in entries(config, &feature) {
+            if let FeatureEntry::Feature(name) = FeatureEntry::parse(entry) {
+                pending.push(name.to_string());
+            }
+        }
+    }
+    Ok(enabled)
+}
+
+fn entries<'a>(config: &'a CpamConfig, feature: &str) -> &'a [String] {
+    config.features.as_ref().and_then(|features| features.get(feature)).map(Vec::as_slice).unwrap_or_default()
+}
+
+// 有効な feature で使わない optional な依存ライブラリを cpam.toml の内容から外す
+pub fn remove_disabled_dependencies(config: &mut CpamConfig, enabled: &BTreeSet<String>) {
+    let used: BTreeSet<String> = enabled
+        .iter()
+        .flat_map(|feature| entries(config, feature))
+        .filter_map(|entry| match FeatureEntry::parse(entry) {
+            FeatureEntry::Dependency(name) => Some(name.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    for kind in DependencyKind::ALL {
+        if let Some(dependencies) = config.dependency_table_mut(kind) {
+            dependencies.retain(|name, dependency| !dependency.is_optional() || used.contains(name));
+        }
+    }
+}
+
+// 有効な feature のコンパイル時の定義（feature ごとの CPAM_FEATURE_<名前> と "define:" の指定）
+pub fn definitions(config: &CpamConfig, enabled: &BTreeSet<String>) -> Vec<String> {
+    let mut definitions = Vec::new();
+    for feature in enabled.iter().filter(|feature| feature.as_str() != DEFAULT_FEATURE) {
+        definitions.push(definition_name(feature));
+        for entry in entries(config, feature) {
+            if let FeatureEntry::Definition(definition) = FeatureEntry::parse(entry) {
+                if !definitions.iter().any(|existing| existing == definition) {
+                    definitions.push(definition.to_string());
+                }
+            }
+        }
+    }
+    definitions
+}
+
+// feature に対応するコンパイル時の定義名（定義はメンバーごとのターゲットに付くので、ワークスペースでも同じ名前）
+pub fn definition_name(feature: &str) -> String {
+    format!("CPAM_FEATURE_{}", upper_identifier(feature))
+}
+
+// feature の ON/OFF を公開する CMake のキャッシュ変数名
+// キャッシュはワークスペース全体で共有されるので、メンバーでは CPAM_<メンバー>_FEATURE_<名前> にする
+pub fn cmake_variable(config: &CpamConfig, feature: &str) -> String {
+    match &config.project {
+        Some(project) if config.workspace_member => {
+            format!("CPAM_{}_FEATURE_{}", upper_identifier(&project.name), upper_identifier(feature))
+        }
+        _ => definition_name(feature),
+    }
+}
+
+fn upper_identifier(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(content: &str) -> CpamConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    const PROJECT: &str = "[project]\nname = \"my-lib\"\nlanguage = \"cpp\"\nbuild_tool = \"make\"\nproject_type = \"lib\"\n";
+
+    fn features(content: &str) -> CpamConfig {
+        config(&format!(
+            "{}\n[dependencies]\nzlib = {{ path = \"zlib\", optional = true }}\nfmt = {{ path = \"fmt\" }}\n\n[dev-dependencies]\ngtest = {{ path = \"gtest\", optional = true }}\n\n[features]\n{}",
+            PROJECT, content
+        ))
+    }
+
+    fn names(enabled: &BTreeSet<String>) -> Vec<&str> {
+        enabled.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn selects_default_and_included_features() {
+        let config = features("default = [\"compress\"]\ncompress = [\"dep:zlib\", \"fast\"]\nfast = [\"define:FAST=1\"]\ntesting = [\"dep:gtest\"]\n");
+        assert_eq!(names(&select(&config, &[], false, false).unwrap()), ["compress", "default", "fast"]);
+        assert!(select(&config, &[], false, true).unwrap().is_empty());
+        assert_eq!(names(&select(&config, &["testing".to_string(), " ".to_string()], false, true).unwrap()), ["testing"]);
+        assert_eq!(names(&select(&config, &[], true, true).unwrap()), ["compress", "default", "fast", "testing"]);
+    }
+
+    #[test]
+    fn rejects_unknown_requested_features() {
+        let config = features("fast = []\n");
+        assert_eq!(select(&config, &["slow".to_string()], false, false).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn validates_feature_entries() {
+        assert!(validate(&features("a = [\"dep:zlib\", \"define:A\", \"define:B=2\", \"b\"]\nb = []\n")).is_ok());
+        assert!(validate(&features("a = [\"dep:fmt\"]\n")).unwrap_err().to_string().contains("optional"));
+        assert!(validate(&features("a = [\"dep:boost\"]\n")).is_err());
+        assert!(validate(&features("a = [\"define:BAD-NAME\"]\n")).is_err());
+        assert!(validate(&features("a = [\"missing\"]\n")).is_err());
+    }
+
+    #[test]
+    fn cyclic_features_terminate() {
+        let config = features("a = [\"b\"]\nb = [\"a\"]\n");
+        assert_eq!(names(&select(&config, &["a".to_string()], false, false).unwrap()), ["a", "b"]);
+    }
+
+    #[test]
+    fn removes_optional_dependencies_of_disabled_features() {
+        let mut config = features("compress = [\"dep:zlib\"]\ntesting = [\"dep:gtest\"]\n");
+        let enabled = select(&config, &["compress".to_string()], false, false).unwrap();
+        remove_disabled_dependencies(&mut config, &enabled);
+
+        let dependencies = config.dependencies.as_ref().unwrap();
+        assert!(dependencies.contains_key("zlib") && dependencies.contains_key("fmt"));
+        assert!(config.dev_dependencies.as_ref().unwrap().is_empty());
+    }
+
+    #[test]
+    fn definitions_skip_default_and_duplicates() {
+        let config = features("default = [\"a\"]\na = [\"define:SHARED\", \"define:A=1\"]\nb = [\"define:SHARED\"]\n");
+        let enabled = select(&config, &["b".to_string()], false, false).unwrap();
+        assert_eq!(definitions(&config, &enabled), ["CPAM_FEATURE_A", "SHARED", "A=1", "CPAM_FEATURE_B"]);
+    }
+
+    #[test]
+    fn workspace_members_prefix_cache_variables() {
+        let mut config = config(PROJECT);
+        assert_eq!(cmake_variable(&config, "json-io"), "CPAM_FEATURE_JSON_IO");
+        config.workspace_member = true;
+        assert_eq!(cmake_variable(&config, "json-io"), "CPAM_MY_LIB_FEATURE_JSON_IO");
+        assert_eq!(definition_name("json-io"), "CPAM_FEATURE_JSON_IO");
+    }
+}
@@ -0,0 +1,359 @@
+use crate::lockfile::LockedPackage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// ネットワークを使わずキャッシュのみで動作するか
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+// オフラインモードで必要なものがキャッシュに無い場合のエラー
+pub fn offline_error(what: &str) -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        format!("オフラインモードですが {} がキャッシュにありません。ネットワークに接続した環境で一度 cpam build を実行してください。", what),
+    )
+}
+
+// 取得したソースを保存するディレクトリ（~/.cache/cpam など）
+pub fn cache_dir() -> io::Result<PathBuf> {
+    dirs::cache_dir()
+        .map(|dir| dir.join("cpam"))
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "キャッシュディレクトリが見つかりません。"))
+}
+
+// URL からキャッシュ内で使うディレクトリ名を作る
+fn cache_key(url: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let base = url
+        .trim_end_matches('/')
+        .rsplit(['/', '\\', ':'])
+        .next()
+        .unwrap_or("source")
+        .trim_end_matches(".git");
+    let base: String = base.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_' || *c == '.').collect();
+    format!("{}-{}", base, &hash[..16])
+}
+
+fn run_git(args: &[&str], git_dir: Option<&Path>) -> io::Result<()> {
+    let mut command = Command::new("git");
+    if let Some(git_dir) = git_dir {
+        command.arg("--git-dir").arg(git_dir);
+    }
+    let output = command.args(args).output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "git {} が失敗しました: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+// コミットを含む Git リポジトリのミラーを用意する
+pub fn git_mirror(url: &str, commit: &str) -> io::Result<PathBuf> {
+    let mirror = cache_dir()?.join("git").join("db").join(cache_key(url));
+    let commit_ref = format!("{}^{{commit}}", commit);
+
+    if is_offline() {
+        if run_git(&["cat-file", "-e", &commit_ref], Some(&mirror)).is_err() {
+            return Err(offline_error(&format!("{} のコミット {}", url, commit)));
+        }
+        return Ok(mirror);
+    }
+
+    if !mirror.exists() {
+        fs::create_dir_all(mirror.parent().unwrap_or(Path::new(".")))?;
+        println!("{} を取得中...", url);
+        run_git(&["clone", "-q", "--mirror", url, &mirror.to_string_lossy()], None)?;
+    } else if run_git(&["cat-file", "-e", &commit_ref], Some(&mirror)).is_err() {
+        println!("{} を更新中...", url);
+        run_git(&["fetch", "-q", "--tags", "origin"], Some(&mirror))?;
+    }
+
+    // ブランチやタグから辿れないコミットは直接取得を試みる
+    if run_git(&["cat-file", "-e", &commit_ref], Some(&mirror)).is_err() {
+        run_git(&["fetch", "-q", "origin", commit], Some(&mirror)).map_err(|_| Error::new(
+            ErrorKind::NotFound,
+            format!("コミット {} が {} に見つかりません。", commit, url),
+        ))?;
+    }
+
+    Ok(mirror)
+}
+
+// git ls-remote に渡す取得先（オフラインモードではキャッシュのミラーを使う）
+pub fn git_remote(url: &str) -> io::Result<String> {
+    if !is_offline() {
+        return Ok(url.to_string());
+    }
+
+    let mirror = cache_dir()?.join("git").join("db").join(cache_key(url));
+    if !mirror.exists() {
+        return Err(offline_error(url));
+    }
+    Ok(mirror.to_string_lossy().into_owned())
+}
+
+// 指定コミットをチェックアウトしたディレクトリを返す（リポジトリとコミットごとに共有する）
+pub fn checkout_git(url: &str, commit: &str) -> io::Result<PathBuf> {
+    let checkout = cache_dir()?.join("git").join("checkouts").join(cache_key(url)).join(commit);
+    if checkout.exists() {
+        return Ok(checkout);
+    }
+
+    let mirror = git_mirror(url, commit)?;
+    let partial = checkout.with_extension("partial");
+    if partial.exists() {
+        fs::remove_dir_all(&partial)?;
+    }
+    fs::create_dir_all(partial.parent().unwrap_or(Path::new(".")))?;
+
+    run_git(&["clone", "-q", "--no-checkout", &mirror.to_string_lossy(), &partial.to_string_lossy()], None)?;
+    run_git(&["-C", &partial.to_string_lossy(), "checkout", "-q", "--detach", commit], None)?;
+    fs::rename(&partial, &checkout)?;
+    Ok(checkout)
+}
+
+// ロックされたパッケージのソースをキャッシュに用意する（パス指定の場合は None）
+pub fn materialize(package: &LockedPackage) -> io::Result<Option<PathBuf>> {
+    if let (Some(git), Some(commit)) = (&package.git, &package.commit) {
+        return checkout_git(git, commit).map(Some);
+    }
+    if let Some(url) = &package.url {
+        return extract_archive(url, package.checksum.as_deref()).map(Some);
+    }
+    Ok(None)
+}
+
+// cpam vendor でソースをコピーするディレクトリ
+pub const VENDOR_DIR: &str = "vendor";
+
+// vendor 内の各パッケージに置く、コピー元を記録したファイル
+pub const VENDOR_STAMP_NAME: &str = ".cpam-vendor.toml";
+
+// vendor にコピーしたパッケージの取得元
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct VendorStamp {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub commit: Option<String>,
+    pub checksum: Option<String>,
+}
+
+impl VendorStamp {
+    pub fn new(package: &LockedPackage) -> Self {
+        VendorStamp {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: package.source.clone(),
+            commit: package.commit.clone(),
+            checksum: package.checksum.clone(),
+        }
+    }
+}
+
+// ロック内容と一致する vendor のコピーがあればそのディレクトリを返す（パス依存は常に元のディレクトリを使う）
+pub fn vendored(package: &LockedPackage) -> Option<PathBuf> {
+    if package.path.is_some() {
+        return None;
+    }
+    let dir = Path::new(VENDOR_DIR).join(&package.name);
+    let stamp_str = fs::read_to_string(dir.join(VENDOR_STAMP_NAME)).ok()?;
+    let stamp: VendorStamp = toml::from_str(&stamp_str).ok()?;
+    if stamp != VendorStamp::new(package) {
+        return None;
+    }
+    std::path::absolute(dir).ok()
+}
+
+// 指定コミットのファイルを読む（存在しない場合は None）
+pub fn read_git_file(url: &str, commit: &str, file: &str) -> io::Result<Option<String>> {
+    let mirror = git_mirror(url, commit)?;
+    let output = Command::new("git")
+        .arg("--git-dir")
+        .arg(&mirror)
+        .args(["show", &format!("{}:{}", commit, file)])
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+// アーカイブをキャッシュへダウンロードする（取得済みならそのまま返す）
+pub fn download_archive(url: &str) -> io::Result<PathBuf> {
+    let file_name = url.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("archive");
+    let archive = cache_dir()?.join("archives").join(cache_key(url)).join(file_name);
+    if archive.exists() {
+        return Ok(archive);
+    }
+    if is_offline() {
+        return Err(offline_error(url));
+    }
+
+    fs::create_dir_all(archive.parent().unwrap_or(Path::new(".")))?;
+    println!("{} をダウンロード中...", url);
+
+    // 途中で中断しても壊れたファイルが残らないよう一時ファイルへ保存する
+    let partial = archive.with_extension("part");
+    let status = Command::new("curl").args(["-fsSL", "-o"]).arg(&partial).arg(url).status()?;
+    if !status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(Error::other(format!("{} のダウンロードに失敗しました。", url)));
+    }
+
+    fs::rename(&partial, &archive)?;
+    Ok(archive)
+}
+
+pub fn sha256_of_file(path: &Path) -> io::Result<String> {
+    let data = fs::read(path)?;
+    Ok(format!("{:x}", Sha256::digest(&data)))
+}
+
+// アーカイブのハッシュが記録と一致しない場合のエラー
+#[derive(Debug)]
+pub struct ChecksumMismatch {
+    pub url: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} の sha256 が一致しません（期待値: {}, 実際: {}）。アーカイブが改ざんされたか、取得元で差し替えられた可能性があります。",
+            self.url, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+// エラーがハッシュの不一致によるものか
+pub fn is_checksum_mismatch(error: &Error) -> bool {
+    error.get_ref().is_some_and(|inner| inner.is::<ChecksumMismatch>())
+}
+
+// ダウンロードしたアーカイブのハッシュを検証する（不一致ならキャッシュから削除する）
+pub fn verify_archive(url: &str, archive: &Path, sha256: &str) -> io::Result<()> {
+    let actual = sha256_of_file(archive)?;
+    if actual.eq_ignore_ascii_case(sha256) {
+        return Ok(());
+    }
+
+    fs::remove_file(archive)?;
+    let extracted = archive.with_extension("src");
+    if extracted.exists() {
+        fs::remove_dir_all(&extracted)?;
+    }
+
+    Err(Error::new(
+        ErrorKind::InvalidData,
+        ChecksumMismatch {
+            url: url.to_string(),
+            expected: sha256.to_lowercase(),
+            actual,
+        },
+    ))
+}
+
+// アーカイブを展開したディレクトリを返す（トップレベルのディレクトリが1つだけならその中）
+// sha256 が指定されていれば展開前に検証する
+pub fn extract_archive(url: &str, sha256: Option<&str>) -> io::Result<PathBuf> {
+    let archive = download_archive(url)?;
+    if let Some(sha256) = sha256 {
+        verify_archive(url, &archive, sha256)?;
+    }
+    let target = archive.with_extension("src");
+
+    if !target.exists() {
+        let partial = archive.with_extension("extracting");
+        if partial.exists() {
+            fs::remove_dir_all(&partial)?;
+        }
+        fs::create_dir_all(&partial)?;
+
+        // tar は zip を展開できない環境があるので unzip を使う
+        let status = if archive.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("zip")) {
+            Command::new("unzip")
+                .arg("-q")
+                .arg(&archive)
+                .arg("-d")
+                .arg(&partial)
+                .status()
+                .map_err(|e| Error::new(e.kind(), format!("unzip の実行に失敗: {}", e)))?
+        } else {
+            Command::new("tar").arg("-xf").arg(&archive).arg("-C").arg(&partial).status()?
+        };
+        if !status.success() {
+            return Err(Error::other(format!("{} の展開に失敗しました。", archive.display())));
+        }
+        fs::rename(&partial, &target)?;
+    }
+
+    let entries: Vec<PathBuf> = fs::read_dir(&target)?.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    match entries.as_slice() {
+        [single] if single.is_dir() => Ok(single.clone()),
+        _ => Ok(target),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    // テストごとに別の一時ディレクトリにアーカイブを置く
+    fn temp_archive(name: &str, content: &[u8]) -> (TempDir, PathBuf) {
+        let dir = TempDir::new(name);
+        let archive = dir.join("lib.tar.gz");
+        fs::write(&archive, content).unwrap();
+        (dir, archive)
+    }
+
+    #[test]
+    fn verify_archive_accepts_matching_hash() {
+        let (_dir, archive) = temp_archive("match", b"hello");
+        let sha256 = "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824";
+        assert!(verify_archive("https://example.com/lib.tar.gz", &archive, sha256).is_ok());
+        assert!(archive.exists());
+    }
+
+    #[test]
+    fn verify_archive_rejects_mismatch_and_removes_cache() {
+        let (_dir, archive) = temp_archive("mismatch", b"tampered");
+        let extracted = archive.with_extension("src");
+        fs::create_dir_all(&extracted).unwrap();
+
+        let expected = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let error = verify_archive("https://example.com/lib.tar.gz", &archive, expected).unwrap_err();
+        assert!(is_checksum_mismatch(&error));
+        assert!(error.to_string().contains(expected));
+        assert!(!archive.exists());
+        assert!(!extracted.exists());
+    }
+
+    #[test]
+    fn other_errors_are_not_checksum_mismatch() {
+        assert!(!is_checksum_mismatch(&Error::other("ダウンロードに失敗")));
+        assert!(!is_checksum_mismatch(&Error::from(ErrorKind::NotFound)));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::config::{CpamConfig, Dependency, DependencySource};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+// cpam.toml と同じ場所に置くロックファイル名
+pub const LOCKFILE_NAME: &str = "cpam.lock";
+
+// ロックファイル形式のバージョン
+const LOCKFILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+// 解決済みの依存ライブラリ
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockedPackage {
+    pub name: String,
+    // 解決されたバージョン（タグ名やブランチ名など）
+    pub version: String,
+    // cpam.toml に書かれたバージョン指定
+    pub requirement: String,
+    pub source: String,
+    // 実際の取得先（git, url, path のいずれか）
+    pub git: Option<String>,
+    pub url: Option<String>,
+    pub path: Option<String>,
+    pub commit: Option<String>,
+    pub checksum: Option<String>,
+    // システムのライブラリを探す方法（find_package のパッケージ名か pkg-config のモジュール名）
+    pub find_package: Option<String>,
+    pub pkg_config: Option<String>,
+    // リンクするCMakeターゲット（省略時はパッケージ名）
+    pub targets: Option<Vec<String>>,
+    // このパッケージが依存するパッケージ名
+    pub dependencies: Option<Vec<String>>,
+    // パス依存の cpam.toml の sha256（手元で依存関係を書き換えたことを検出する）
+    pub manifest_checksum: Option<String>,
+    // cpam.toml の [patch] で取得元を差し替えたか
+    pub patched: Option<bool>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            packages: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    // ロックファイルを読み込む（存在しない場合は None）
+    pub fn load() -> io::Result<Option<Self>> {
+        let lock_path = Path::new(LOCKFILE_NAME);
+        if !lock_path.exists() {
+            return Ok(None);
+        }
+
+        let lock_str = fs::read_to_string(lock_path)?;
+        let lockfile: Lockfile = toml::from_str(&lock_str)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{} の解析に失敗: {}", LOCKFILE_NAME, e)))?;
+
+        if lockfile.version != LOCKFILE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} の形式バージョン {} には対応していません。", LOCKFILE_NAME, lockfile.version),
+            ));
+        }
+
+        Ok(Some(lockfile))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let toml_str = toml::to_string(self)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("ロックファイルのシリアライズに失敗: {}", e)))?;
+
+        let content = format!("# このファイルは cpam によって自動生成されます。手動で編集しないでください。\n{}", toml_str);
+        fs::write(LOCKFILE_NAME, content)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+
+    // cpam.toml の依存関係とロック内容が一致しているか確認する
+    pub fn is_up_to_date(&self, config: &CpamConfig) -> io::Result<bool> {
+        let dependencies = config.cpam_dependencies()?;
+        for (name, dependency) in &dependencies {
+            let dependency = config.patched(name, dependency).unwrap_or_else(|| (*dependency).clone());
+            match self.find(name) {
+                Some(package) if package.matches(&dependency)? => {}
+                _ => return Ok(false),
+            }
+        }
+
+        // [patch] の追加・変更・削除を推移的な依存も含めて確認する
+        for package in &self.packages {
+            let original = Dependency::Version(package.requirement.clone());
+            let up_to_date = match config.patched(&package.name, &original) {
+                Some(patched) => package.patched == Some(true) && package.matches(&patched)?,
+                None => package.patched != Some(true),
+            };
+            if !up_to_date {
+                return Ok(false);
+            }
+        }
+
+        // パス依存は手元で編集されるので、その cpam.toml の変更も確認する
+        for package in &self.packages {
+            if package.manifest_checksum != package.current_manifest_checksum()? {
+                return Ok(false);
+            }
+        }
+
+        // 推移的な依存を含め、どこからも参照されないパッケージが残っていないか確認する
+        let mut reachable: Vec<&str> = dependencies.keys().copied().collect();
+        let root = config.project.as_ref().map(|project| project.name.as_str());
+        let mut index = 0;
+        while index < reachable.len() {
+            let package = match self.find(reachable[index]) {
+                Some(package) => package,
+                None if Some(reachable[index]) == root => {
+                    index += 1;
+                    continue;
+                }
+                None => return Ok(false),
+            };
+            if let Some(children) = &package.dependencies {
+                for child in children {
+                    if !reachable.contains(&child.as_str()) {
+                        reachable.push(child);
+                    }
+                }
+            }
+            index += 1;
+        }
+
+        Ok(self.packages.iter().all(|package| reachable.contains(&package.name.as_str())))
+    }
+}
+
+impl LockedPackage {
+    // cpam.toml の指定からこのロック内容が得られるか確認する
+    pub fn matches(&self, dependency: &Dependency) -> io::Result<bool> {
+        if self.requirement != dependency.version() {
+            return Ok(false);
+        }
+
+        let source = dependency.source()?;
+        if let DependencySource::Archive { sha256: Some(sha256), .. } = &source {
+            if !self.checksum.as_deref().is_some_and(|checksum| checksum.eq_ignore_ascii_case(sha256)) {
+                return Ok(false);
+            }
+        }
+
+        if dependency.targets().is_some_and(|targets| self.targets.as_deref() != Some(targets)) {
+            return Ok(false);
+        }
+
+        Ok(self.source == source.id())
+    }
+
+    // パス依存の cpam.toml の現在の sha256（パス依存でないか cpam.toml が無い場合は None）
+    // チェックアウト時の改行コードの違いでロックが古くならないよう、改行を揃えてから計算する
+    pub fn current_manifest_checksum(&self) -> io::Result<Option<String>> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        let manifest = Path::new(path).join("cpam.toml");
+        if !manifest.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&manifest)?.replace("\r\n", "\n");
+        Ok(Some(format!("{:x}", Sha256::digest(content.as_bytes()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve;
+    use crate::test_support::TempDir;
+
+    fn write_manifest(dir: &Path, content: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("cpam.toml"), content).unwrap();
+    }
+
+    fn config(content: &str) -> CpamConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    // app -> a -> b のパス依存
+    fn project(name: &str) -> (TempDir, CpamConfig) {
+        let dir = TempDir::new(name);
+        write_manifest(&dir.join("b"), "");
+        write_manifest(&dir.join("a"), "[dependencies]\nb = { path = \"../b\" }\n");
+        let config = config(&format!("[dependencies]\na = {{ path = \"{}\" }}\n", dir.join("a").display()));
+        (dir, config)
+    }
+
+    #[test]
+    fn fresh_lock_is_up_to_date() {
+        let (_dir, config) = project("fresh");
+        let lockfile = resolve::resolve(&config, None).unwrap();
+        assert!(lockfile.is_up_to_date(&config).unwrap());
+    }
+
+    #[test]
+    fn editing_path_dependency_manifest_makes_lock_stale() {
+        let (dir, config) = project("manifest");
+        let lockfile = resolve::resolve(&config, None).unwrap();
+
+        write_manifest(&dir.join("c"), "");
+        write_manifest(&dir.join("a"), "[dependencies]\nb = { path = \"../b\" }\nc = { path = \"../c\" }\n");
+        assert!(!lockfile.is_up_to_date(&config).unwrap());
+
+        let updated = resolve::resolve(&config, Some(&lockfile)).unwrap();
+        assert!(updated.find("c").is_some());
+        assert!(updated.is_up_to_date(&config).unwrap());
+    }
+
+    #[test]
+    fn manifest_checksum_ignores_line_endings() {
+        let (dir, config) = project("crlf");
+        let lockfile = resolve::resolve(&config, None).unwrap();
+        write_manifest(&dir.join("a"), "[dependencies]\r\nb = { path = \"../b\" }\r\n");
+        assert!(lockfile.is_up_to_date(&config).unwrap());
+    }
+
+    #[test]
+    fn changed_or_added_dependency_makes_lock_stale() {
+        let (dir, config) = project("changed");
+        let lockfile = resolve::resolve(&config, None).unwrap();
+
+        let moved = self::config(&format!("[dependencies]\na = {{ path = \"{}\" }}\n", dir.join("b").display()));
+        assert!(!lockfile.is_up_to_date(&moved).unwrap());
+
+        let added = self::config(&format!(
+            "[dependencies]\na = {{ path = \"{}\" }}\nc = {{ path = \"{}\" }}\n",
+            dir.join("a").display(),
+            dir.join("c").display()
+        ));
+        assert!(!lockfile.is_up_to_date(&added).unwrap());
+    }
+
+    #[test]
+    fn unreachable_package_makes_lock_stale() {
+        let (dir, config) = project("unreachable");
+        let mut lockfile = resolve::resolve(&config, None).unwrap();
+        let mut orphan = lockfile.find("b").unwrap().clone();
+        orphan.name = "orphan".to_string();
+        lockfile.packages.push(orphan);
+        assert!(!lockfile.is_up_to_date(&config).unwrap());
+
+        let removed = self::config(&format!("[dependencies]\nb = {{ path = \"{}\" }}\n", dir.join("b").display()));
+        assert!(!resolve::resolve(&config, None).unwrap().is_up_to_date(&removed).unwrap());
+    }
+
+    #[test]
+    fn adding_or_removing_patch_makes_lock_stale() {
+        let (dir, config) = project("patch");
+        let lockfile = resolve::resolve(&config, None).unwrap();
+
+        write_manifest(&dir.join("b-fork"), "");
+        let patched = self::config(&format!(
+            "[dependencies]\na = {{ path = \"{}\" }}\n\n[patch]\nb = {{ path = \"{}\" }}\n",
+            dir.join("a").display(),
+            dir.join("b-fork").display()
+        ));
+        assert!(!lockfile.is_up_to_date(&patched).unwrap());
+
+        let patched_lock = resolve::resolve(&patched, Some(&lockfile)).unwrap();
+        assert_eq!(patched_lock.find("b").unwrap().patched, Some(true));
+        assert!(patched_lock.is_up_to_date(&patched).unwrap());
+        assert!(!patched_lock.is_up_to_date(&config).unwrap());
+    }
+}
@@ -1,18 +1,43 @@
 use clap::Parser;
 mod cli;
+mod cmake;
+mod cmakelists;
 mod commands;
+mod conan;
 mod config;
+mod features;
+mod fetch;
+mod lockfile;
+mod platform;
+mod registry;
+mod resolve;
+mod standard;
+#[cfg(test)]
+mod test_support;
+mod vcpkg;
+mod version;
+mod workspace;
 
 use cli::{Cli, Commands};
 
 fn main() {
     let cli = Cli::parse();
 
-    match &cli.command {
+    let result = match &cli.command {
         Commands::New(args) => commands::new::execute(args),
         Commands::Add(args) => commands::add::execute(args),
         Commands::Remove(args) => commands::remove::execute(args),
         Commands::Build(args) => commands::build::execute(args),
         Commands::Run(args) => commands::run::execute(args),
+        Commands::Tree(args) => commands::tree::execute(args),
+        Commands::Update(args) => commands::update::execute(args),
+        Commands::Outdated(args) => commands::outdated::execute(args),
+        Commands::Vendor(args) => commands::vendor::execute(args),
+    };
+
+    // 失敗したコマンドは終了コード 1 で終える
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,174 @@
+use std::env::consts;
+use std::io::{self, Error, ErrorKind};
+
+// platforms に書ける条件（OS, アーキテクチャ, コンパイラ）
+// 1つの条件は "linux" や "linux+aarch64" のように "+" で組み合わせられ、いずれかの条件に一致すれば有効になる
+const OS_TERMS: &[(&str, &str)] = &[
+    ("linux", "CMAKE_SYSTEM_NAME STREQUAL \"Linux\""),
+    ("windows", "CMAKE_SYSTEM_NAME STREQUAL \"Windows\""),
+    ("macos", "CMAKE_SYSTEM_NAME STREQUAL \"Darwin\""),
+    ("ios", "CMAKE_SYSTEM_NAME STREQUAL \"iOS\""),
+    ("android", "CMAKE_SYSTEM_NAME STREQUAL \"Android\""),
+    ("freebsd", "CMAKE_SYSTEM_NAME STREQUAL \"FreeBSD\""),
+    ("unix", "UNIX"),
+    ("apple", "APPLE"),
+];
+
+const ARCH_TERMS: &[(&str, &str)] = &[
+    ("x86_64", "CMAKE_SYSTEM_PROCESSOR MATCHES \"^(x86_64|AMD64|amd64)$\""),
+    ("x86", "CMAKE_SYSTEM_PROCESSOR MATCHES \"^(i[3-6]86|x86|X86)$\""),
+    ("aarch64", "CMAKE_SYSTEM_PROCESSOR MATCHES \"^(aarch64|arm64|ARM64)$\""),
+    ("arm", "CMAKE_SYSTEM_PROCESSOR MATCHES \"^arm\""),
+];
+
+// コンパイラの条件（"{}" はプロジェクトの言語に置き換える）
+const COMPILER_TERMS: &[(&str, &str)] = &[
+    ("gcc", "CMAKE_{}_COMPILER_ID STREQUAL \"GNU\""),
+    ("clang", "CMAKE_{}_COMPILER_ID MATCHES \"Clang\""),
+    ("msvc", "MSVC"),
+];
+
+// 条件の書式を確認する
+pub fn validate(platforms: &[String]) -> io::Result<()> {
+    if platforms.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "platforms には1つ以上の条件を指定してください。"));
+    }
+    for term in platforms.iter().flat_map(|platform| platform.split('+')) {
+        let known = [OS_TERMS, ARCH_TERMS, COMPILER_TERMS].iter().any(|terms| terms.iter().any(|(name, _)| *name == term));
+        if !known {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "platforms の条件 '{}' は不明です（{} のいずれかを \"+\" で組み合わせてください）。",
+                    term,
+                    [OS_TERMS, ARCH_TERMS, COMPILER_TERMS].iter().flat_map(|terms| terms.iter().map(|(name, _)| *name)).collect::<Vec<_>>().join(", ")
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// CMake の if() に渡す条件式
+pub fn cmake_condition(platforms: &[String], language: &str) -> String {
+    let conditions: Vec<String> = platforms
+        .iter()
+        .map(|platform| {
+            let terms: Vec<String> = platform
+                .split('+')
+                .filter_map(|term| {
+                    [OS_TERMS, ARCH_TERMS, COMPILER_TERMS]
+                        .iter()
+                        .find_map(|terms| terms.iter().find(|(name, _)| *name == term))
+                        .map(|(_, condition)| condition.replace("{}", language))
+                })
+                .collect();
+            terms.join(" AND ")
+        })
+        .collect();
+    if conditions.len() == 1 {
+        return conditions[0].clone();
+    }
+    conditions.iter().map(|condition| format!("({})", condition)).collect::<Vec<_>>().join(" OR ")
+}
+
+// cpam を実行しているホストが条件に一致するか（コンパイラの条件は確認できないので一致とみなす）
+pub fn matches_host(platforms: &[String]) -> bool {
+    platforms.iter().any(|platform| {
+        platform.split('+').all(|term| match term {
+            "unix" => cfg!(unix),
+            "apple" => consts::OS == "macos" || consts::OS == "ios",
+            term if OS_TERMS.iter().any(|(name, _)| *name == term) => consts::OS == term,
+            term if ARCH_TERMS.iter().any(|(name, _)| *name == term) => consts::ARCH == term,
+            _ => true,
+        })
+    })
+}
+
+// vcpkg.json の "platform" に書く式
+// vcpkg はコンパイラを条件にできないため、コンパイラだけの条件があれば常に有効（None）とする
+pub fn vcpkg_expression(platforms: &[String]) -> Option<String> {
+    let mut conditions = Vec::new();
+    for platform in platforms {
+        let terms: Vec<&str> = platform
+            .split('+')
+            .filter_map(|term| match term {
+                "macos" => Some("osx"),
+                "x86_64" => Some("x64"),
+                "aarch64" => Some("arm64"),
+                "apple" => Some("(osx | ios)"),
+                // vcpkg には unix が無いので、CMake の UNIX と同じく Windows 以外とみなす
+                "unix" => Some("!windows"),
+                term if COMPILER_TERMS.iter().any(|(name, _)| *name == term) => None,
+                term => Some(term),
+            })
+            .collect();
+        if terms.is_empty() {
+            return None;
+        }
+        conditions.push(terms.join(" & "));
+    }
+
+    if conditions.len() == 1 {
+        return conditions.pop();
+    }
+    Some(conditions.iter().map(|condition| format!("({})", condition)).collect::<Vec<_>>().join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platforms(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn validates_terms() {
+        assert!(validate(&platforms(&["linux", "windows+msvc", "macos+aarch64+clang"])).is_ok());
+        assert!(validate(&platforms(&["linux+solaris"])).unwrap_err().to_string().contains("'solaris'"));
+        assert!(validate(&platforms(&["linux+"])).is_err());
+        assert!(validate(&[]).is_err());
+    }
+
+    #[test]
+    fn builds_cmake_conditions() {
+        assert_eq!(cmake_condition(&platforms(&["unix"]), "CXX"), "UNIX");
+        assert_eq!(
+            cmake_condition(&platforms(&["linux+gcc"]), "C"),
+            "CMAKE_SYSTEM_NAME STREQUAL \"Linux\" AND CMAKE_C_COMPILER_ID STREQUAL \"GNU\""
+        );
+        assert_eq!(
+            cmake_condition(&platforms(&["windows", "apple+aarch64"]), "CXX"),
+            "(CMAKE_SYSTEM_NAME STREQUAL \"Windows\") OR (APPLE AND CMAKE_SYSTEM_PROCESSOR MATCHES \"^(aarch64|arm64|ARM64)$\")"
+        );
+    }
+
+    #[test]
+    fn builds_vcpkg_expressions() {
+        assert_eq!(vcpkg_expression(&platforms(&["linux"])).as_deref(), Some("linux"));
+        assert_eq!(vcpkg_expression(&platforms(&["macos+x86_64"])).as_deref(), Some("osx & x64"));
+        assert_eq!(vcpkg_expression(&platforms(&["windows", "apple"])).as_deref(), Some("(windows) | ((osx | ios))"));
+        assert_eq!(vcpkg_expression(&platforms(&["windows+msvc"])).as_deref(), Some("windows"));
+    }
+
+    #[test]
+    fn maps_unix_to_not_windows_for_vcpkg() {
+        assert_eq!(vcpkg_expression(&platforms(&["unix"])).as_deref(), Some("!windows"));
+        assert_eq!(vcpkg_expression(&platforms(&["unix+aarch64"])).as_deref(), Some("!windows & arm64"));
+    }
+
+    #[test]
+    fn compiler_only_conditions_are_always_enabled_for_vcpkg() {
+        assert_eq!(vcpkg_expression(&platforms(&["msvc"])), None);
+        assert_eq!(vcpkg_expression(&platforms(&["linux", "clang"])), None);
+    }
+
+    #[test]
+    fn matches_host_ignores_compilers() {
+        assert!(matches_host(&platforms(&[consts::OS])));
+        assert!(matches_host(&platforms(&["gcc"])));
+        assert_eq!(matches_host(&platforms(&["unix"])), cfg!(unix));
+        assert!(!matches_host(&platforms(&["linux+windows"])));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::config::{CpamConfig, UserConfig};
+use crate::fetch;
+use crate::version;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// レジストリのインデックスファイル（<レジストリ>/<パッケージ名>.toml）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexEntry {
+    pub name: String,
+    #[serde(default, rename = "version")]
+    pub versions: Vec<IndexVersion>,
+}
+
+// パッケージの各バージョンの取得元とCMakeターゲット
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexVersion {
+    pub version: String,
+    pub git: Option<String>,
+    pub tag: Option<String>,
+    pub rev: Option<String>,
+    pub url: Option<String>,
+    pub sha256: Option<String>,
+    pub targets: Option<Vec<String>>,
+    pub dependencies: Option<BTreeMap<String, String>>,
+}
+
+pub struct Registry {
+    pub name: String,
+    pub location: String,
+    // 同期済みのインデックスディレクトリ
+    root: RefCell<Option<PathBuf>>,
+}
+
+impl Registry {
+    pub fn new(name: &str, location: &str) -> Self {
+        Registry {
+            name: name.to_string(),
+            location: location.to_string(),
+            root: RefCell::new(None),
+        }
+    }
+
+    // インデックスのディレクトリを返す（Gitリポジトリの場合はキャッシュへ同期する）
+    fn root(&self) -> io::Result<PathBuf> {
+        if let Some(root) = self.root.borrow().as_ref() {
+            return Ok(root.clone());
+        }
+
+        let root = match local_path(&self.location) {
+            Some(path) if !self.location.ends_with(".git") => {
+                if !path.is_dir() {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("レジストリ '{}' のディレクトリ '{}' が存在しません。", self.name, path.display()),
+                    ));
+                }
+                path
+            }
+            _ => self.sync_git()?,
+        };
+
+        *self.root.borrow_mut() = Some(root.clone());
+        Ok(root)
+    }
+
+    fn sync_git(&self) -> io::Result<PathBuf> {
+        let cache_dir = fetch::cache_dir()?.join("registries").join(&self.name);
+        if fetch::is_offline() {
+            if !cache_dir.exists() {
+                return Err(fetch::offline_error(&format!("レジストリ '{}'", self.name)));
+            }
+            return Ok(cache_dir);
+        }
+
+        println!("レジストリ '{}' を同期中...", self.name);
+        let status = if cache_dir.join(".git").exists() {
+            Command::new("git").arg("-C").arg(&cache_dir).args(["pull", "-q", "--ff-only"]).status()?
+        } else {
+            if let Some(parent) = cache_dir.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Command::new("git").args(["clone", "-q", "--depth", "1", &self.location]).arg(&cache_dir).status()?
+        };
+
+        if !status.success() {
+            return Err(Error::other(format!("レジストリ '{}' ({}) の同期に失敗しました。", self.name, self.location)));
+        }
+        Ok(cache_dir)
+    }
+
+    // パッケージのインデックスを読み込む（登録されていない場合は None）
+    pub fn lookup(&self, package: &str) -> io::Result<Option<IndexEntry>> {
+        if package.is_empty() || package.contains(['/', '\\']) || package.starts_with('.') {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("パッケージ名 '{}' は不正です。", package)));
+        }
+
+        let index_path = self.root()?.join(format!("{}.toml", package));
+        if !index_path.exists() {
+            return Ok(None);
+        }
+
+        let index_str = fs::read_to_string(&index_path)?;
+        let entry: IndexEntry = toml::from_str(&index_str).map_err(|e| Error::new(
+            ErrorKind::InvalidData,
+            format!("レジストリ '{}' の {} の解析に失敗: {}", self.name, index_path.display(), e),
+        ))?;
+        Ok(Some(entry))
+    }
+}
+
+impl IndexEntry {
+    pub fn version_names(&self) -> Vec<String> {
+        self.versions.iter().map(|version| version.version.clone()).collect()
+    }
+
+    // 要求を満たす最新のバージョンを選ぶ
+    pub fn select(&self, requirement: &VersionReq) -> Option<(&IndexVersion, Version)> {
+        let names = self.version_names();
+        let (selected, version) = version::select_highest(&names, requirement)?;
+        let entry = self.versions.iter().find(|entry| entry.version == selected)?;
+        Some((entry, version))
+    }
+}
+
+// cpam.toml とユーザー設定に登録されたレジストリ（同名の場合は cpam.toml を優先）
+pub fn configured(config: &CpamConfig) -> io::Result<Vec<Registry>> {
+    let mut registries: Vec<Registry> = Vec::new();
+    let user_config = UserConfig::load()?;
+
+    for table in [&config.registries, &user_config.registries].into_iter().flatten() {
+        for (name, location) in table {
+            if !registries.iter().any(|registry| &registry.name == name) {
+                registries.push(Registry::new(name, location));
+            }
+        }
+    }
+
+    Ok(registries)
+}
+
+// パッケージを登録しているレジストリを探す
+pub fn find<'a>(registries: &'a [Registry], package: &str, registry: Option<&str>) -> io::Result<(&'a Registry, IndexEntry)> {
+    if registries.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("'{}' の取得元が指定されておらず、レジストリも設定されていません。`--source` を指定するか [registries] を設定してください。", package),
+        ));
+    }
+
+    if let Some(name) = registry {
+        let registry = registries.iter().find(|registry| registry.name == name).ok_or_else(|| Error::new(
+            ErrorKind::NotFound,
+            format!("レジストリ '{}' は設定されていません。", name),
+        ))?;
+        return match registry.lookup(package)? {
+            Some(entry) => Ok((registry, entry)),
+            None => Err(Error::new(ErrorKind::NotFound, format!("'{}' はレジストリ '{}' に登録されていません。", package, name))),
+        };
+    }
+
+    for registry in registries {
+        if let Some(entry) = registry.lookup(package)? {
+            return Ok((registry, entry));
+        }
+    }
+
+    Err(Error::new(ErrorKind::NotFound, format!("'{}' はどのレジストリにも登録されていません。", package)))
+}
+
+// "file://" 形式やローカルパスをパスへ変換する
+fn local_path(location: &str) -> Option<PathBuf> {
+    if let Some(path) = location.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if location.contains("://") || location.starts_with("git@") {
+        return None;
+    }
+    Some(Path::new(location).to_path_buf())
+}
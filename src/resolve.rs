@@ -0,0 +1,601 @@
+use crate::config::{CpamConfig, Dependency, DependencySource, GitReference, SystemPackage};
+use crate::fetch;
+use crate::lockfile::{LockedPackage, Lockfile, LOCKFILE_NAME};
+use crate::registry::{self, Registry};
+use crate::version;
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+// 依存関係を解決してロックファイルを返す
+// locked が true の場合はロックファイルの更新を許可しない
+pub fn lock_dependencies(config: &CpamConfig, locked: bool) -> io::Result<Lockfile> {
+    let previous = Lockfile::load()?;
+
+    if let Some(lockfile) = &previous {
+        if lockfile.is_up_to_date(config)? {
+            return Ok(lockfile.clone());
+        }
+    }
+
+    if locked {
+        let reason = if previous.is_some() { "が cpam.toml と一致しません" } else { "が存在しません" };
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} {}。--locked を外して実行し、ロックファイルを更新してください。", LOCKFILE_NAME, reason),
+        ));
+    }
+
+    let lockfile = resolve(config, previous.as_ref())?;
+    lockfile.save()?;
+    println!("{} を更新しました。", LOCKFILE_NAME);
+    Ok(lockfile)
+}
+
+// 依存関係を辿る際の要求
+struct Request {
+    // 要求元のパッケージ名
+    parent: String,
+    name: String,
+    dependency: Dependency,
+}
+
+// cpam.toml の依存関係を推移的に解決する（変更の無い依存は前回のロック内容を再利用する）
+pub fn resolve(config: &CpamConfig, previous: Option<&Lockfile>) -> io::Result<Lockfile> {
+    let registries = registry::configured(config)?;
+    let root = root_name(config);
+
+    let mut packages: Vec<LockedPackage> = Vec::new();
+    // 各パッケージを最初に要求したパッケージ（競合の報告に使う）
+    let mut requesters: BTreeMap<String, String> = BTreeMap::new();
+    let mut queue: VecDeque<Request> = VecDeque::new();
+
+    let dependencies = config.cpam_dependencies()?;
+    for (name, dependency) in &dependencies {
+        queue.push_back(Request { parent: root.clone(), name: name.to_string(), dependency: (*dependency).clone() });
+    }
+
+    // 幅優先で辿り、浅い位置で要求されたパッケージの指定を優先する
+    while let Some(mut request) = queue.pop_front() {
+        // [patch] で差し替えられていれば、要求元に関係なく差し替え先を使う
+        let patched = match config.patched(&request.name, &request.dependency) {
+            Some(dependency) => {
+                request.dependency = dependency;
+                true
+            }
+            None => false,
+        };
+
+        let with_context = |e: Error| Error::new(e.kind(), format!("依存ライブラリ '{}' の解決に失敗: {}", request.name, e));
+
+        // プロジェクト自身への依存は循環として後で報告する
+        if request.name == root {
+            continue;
+        }
+
+        if let Some(existing) = packages.iter().find(|package| package.name == request.name) {
+            check_compatible(existing, &request, &requesters[&request.name])?;
+            continue;
+        }
+
+        let reused = match previous.and_then(|lockfile| lockfile.find(&request.name)) {
+            Some(package) if package.matches(&request.dependency).map_err(with_context)? => Some(package.clone()),
+            _ => None,
+        };
+
+        let mut package = match reused {
+            Some(package) => package,
+            None => resolve_dependency(&request.name, &request.dependency, &registries).map_err(with_context)?,
+        };
+
+        package.patched = patched.then_some(true);
+        package.manifest_checksum = package.current_manifest_checksum().map_err(with_context)?;
+
+        let children = manifest_dependencies(&package, &registries).map_err(with_context)?;
+        package.dependencies = if children.is_empty() {
+            None
+        } else {
+            Some(children.iter().map(|(name, _)| name.clone()).collect())
+        };
+        for (name, dependency) in children {
+            queue.push_back(Request { parent: package.name.clone(), name, dependency });
+        }
+
+        requesters.insert(request.name.clone(), request.parent.clone());
+        packages.push(package);
+    }
+
+    let direct: Vec<String> = dependencies.keys().map(|name| name.to_string()).collect();
+    let order = dependency_order(&root, &direct, &packages)?;
+
+    for name in config.patch.iter().flat_map(|patch| patch.keys()) {
+        if !packages.iter().any(|package| &package.name == name) {
+            println!("警告: [patch] の '{}' はどの依存ライブラリにも使われていません。", name);
+        }
+    }
+
+    // 依存される側が先に来るよう並べ替える
+    let mut lockfile = Lockfile::default();
+    for name in order {
+        if let Some(index) = packages.iter().position(|package| package.name == name) {
+            lockfile.packages.push(packages.swap_remove(index));
+        }
+    }
+    Ok(lockfile)
+}
+
+// 依存グラフの根（プロジェクト自身）の名前
+fn root_name(config: &CpamConfig) -> String {
+    config.project.as_ref().map(|project| project.name.clone()).unwrap_or_else(|| "(root)".to_string())
+}
+
+// 既に解決済みのパッケージが別の要求も満たすか確認する
+fn check_compatible(existing: &LockedPackage, request: &Request, first_requester: &str) -> io::Result<()> {
+    // [patch] の差し替え先は全ての要求元で共通なので、バージョンの要求は確認しない
+    if existing.patched == Some(true) {
+        return Ok(());
+    }
+
+    let source = request.dependency.source()?;
+    let same_location = match &source {
+        DependencySource::Git { url, .. } => existing.git.as_deref() == Some(*url),
+        DependencySource::Archive { url, .. } => existing.url.as_deref() == Some(*url),
+        DependencySource::Path(path) => existing.path.as_deref() == Some(*path),
+        DependencySource::Registry(_) => existing.source.starts_with("registry"),
+        DependencySource::System(_) => existing.source == source.id(),
+    };
+
+    if !same_location {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "'{}' の取得元が競合しています: '{}' は {}、'{}' は {} を指定しています。",
+                request.name, first_requester, existing.source, request.parent, source.id()
+            ),
+        ));
+    }
+
+    // システムのライブラリのバージョンは CMake の設定時に確認される
+    if let DependencySource::System(_) = source {
+        return Ok(());
+    }
+
+    let requirement_str = request.dependency.version();
+    let requirement = version::parse_requirement(requirement_str)?;
+    let satisfied = match version::parse_tag(&existing.version) {
+        Some(version) => requirement.matches(&version),
+        // ブランチなどバージョンとして読めないものは "*" のみ受け付ける
+        None => requirement_str == "*",
+    };
+
+    if !satisfied {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "'{}' のバージョンが競合しています: '{}' の要求 '{}' により {} に解決されましたが、'{}' は '{}' を要求しています。",
+                request.name, first_requester, existing.requirement, existing.version, request.parent, requirement_str
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+// 依存ライブラリ自身の依存関係を読み込む（cpam.toml またはレジストリのインデックスから）
+fn manifest_dependencies(package: &LockedPackage, registries: &[Registry]) -> io::Result<Vec<(String, Dependency)>> {
+    if let Some(registry_name) = package.source.strip_prefix("registry") {
+        let registry_name = registry_name.strip_prefix('+');
+        let (_, entry) = registry::find(registries, &package.name, registry_name)?;
+        let dependencies = entry
+            .versions
+            .iter()
+            .find(|version| version::parse_tag(&version.version).map(|v| v.to_string()).as_deref() == Some(package.version.as_str()))
+            .and_then(|version| version.dependencies.clone())
+            .unwrap_or_default();
+        return Ok(dependencies.into_iter().map(|(name, requirement)| (name, Dependency::Version(requirement))).collect());
+    }
+
+    let (manifest, base) = if let (Some(git), Some(commit)) = (&package.git, &package.commit) {
+        (fetch::read_git_file(git, commit, "cpam.toml")?, None)
+    } else if let Some(url) = &package.url {
+        let manifest_path = fetch::extract_archive(url, package.checksum.as_deref())?.join("cpam.toml");
+        (fs::read_to_string(manifest_path).ok(), None)
+    } else if let Some(path) = &package.path {
+        (fs::read_to_string(Path::new(path).join("cpam.toml")).ok(), Some(PathBuf::from(path)))
+    } else {
+        (None, None)
+    };
+
+    let manifest = match manifest {
+        Some(manifest) => manifest,
+        None => return Ok(Vec::new()),
+    };
+    let config: CpamConfig = toml::from_str(&manifest)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("'{}' の cpam.toml の解析に失敗: {}", package.name, e)))?;
+
+    let mut dependencies = Vec::new();
+    for (name, mut dependency) in config.dependencies.unwrap_or_default() {
+        // パス指定はこのプロジェクトからの相対パスへ置き換える
+        if let Dependency::Detailed(detail) = &mut dependency {
+            if let Some(path) = &mut detail.path {
+                let base = base.as_ref().ok_or_else(|| Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Git やアーカイブから取得した '{}' ではパス指定の依存 '{}' を使用できません。", package.name, name),
+                ))?;
+                *path = normalize_path(&base.join(&*path)).to_string_lossy().replace('\\', "/");
+            }
+        }
+        dependencies.push((name, dependency));
+    }
+
+    Ok(dependencies)
+}
+
+// "a/../b" のような冗長な部分を取り除く
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// 循環を検出しつつ、依存される側が先に来る順序を求める
+fn dependency_order(root: &str, direct: &[String], packages: &[LockedPackage]) -> io::Result<Vec<String>> {
+    fn visit(
+        name: &str,
+        root: &str,
+        packages: &[LockedPackage],
+        path: &mut Vec<String>,
+        done: &mut Vec<String>,
+    ) -> io::Result<()> {
+        if path.iter().any(|visited| visited == name) {
+            let mut cycle = path.clone();
+            cycle.push(name.to_string());
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("依存関係が循環しています: {}", cycle.join(" -> ")),
+            ));
+        }
+        if done.iter().any(|finished| finished == name) {
+            return Ok(());
+        }
+
+        path.push(name.to_string());
+        let children = packages
+            .iter()
+            .find(|package| package.name == name)
+            .and_then(|package| package.dependencies.clone())
+            .unwrap_or_default();
+        for child in children {
+            visit(&child, root, packages, path, done)?;
+        }
+        path.pop();
+
+        if name != root {
+            done.push(name.to_string());
+        }
+        Ok(())
+    }
+
+    let mut path = vec![root.to_string()];
+    let mut done = Vec::new();
+    for name in direct {
+        visit(name, root, packages, &mut path, &mut done)?;
+    }
+    Ok(done)
+}
+
+// 依存ライブラリ1件の取得元を確定させる
+fn resolve_dependency(name: &str, dependency: &Dependency, registries: &[Registry]) -> io::Result<LockedPackage> {
+    let source = dependency.source()?;
+    let requirement_str = dependency.version();
+    let requirement = version::parse_requirement(requirement_str)?;
+
+    println!("依存ライブラリ '{}' を解決中...", name);
+
+    let mut package = LockedPackage {
+        name: name.to_string(),
+        version: requirement_str.to_string(),
+        requirement: requirement_str.to_string(),
+        source: source.id(),
+        git: None,
+        url: None,
+        path: None,
+        commit: None,
+        checksum: None,
+        find_package: None,
+        pkg_config: None,
+        targets: None,
+        dependencies: None,
+        manifest_checksum: None,
+        patched: None,
+    };
+
+    match &source {
+        DependencySource::Git { url, reference } => {
+            let (version, commit) = resolve_git(name, url, reference, &requirement, requirement_str)?;
+            package.version = version;
+            package.git = Some(url.to_string());
+            package.commit = Some(commit);
+        }
+        DependencySource::Archive { url, sha256 } => {
+            let checksum = match sha256 {
+                Some(sha256) => sha256.to_string(),
+                None => fetch::sha256_of_file(&fetch::download_archive(url)?)?,
+            };
+            package.url = Some(url.to_string());
+            package.checksum = Some(checksum);
+        }
+        DependencySource::Path(path) => package.path = Some(path.to_string()),
+        DependencySource::System(SystemPackage::FindPackage(find_package)) => {
+            package.version = "system".to_string();
+            package.find_package = Some(find_package.to_string());
+        }
+        DependencySource::System(SystemPackage::PkgConfig(module)) => {
+            package.version = "system".to_string();
+            package.pkg_config = Some(module.to_string());
+        }
+        DependencySource::Registry(registry_name) => {
+            let (registry, entry) = registry::find(registries, name, *registry_name)?;
+            let (selected, version) = entry
+                .select(&requirement)
+                .ok_or_else(|| version::unsatisfiable(name, requirement_str, &entry.version_names()))?;
+
+            package.version = version.to_string();
+            package.targets = selected.targets.clone();
+            match (&selected.git, &selected.url) {
+                (Some(git), None) => {
+                    let commit = match (&selected.rev, &selected.tag) {
+                        (Some(rev), _) => rev.clone(),
+                        (None, Some(tag)) => git_ls_remote(git, &format!("refs/tags/{}", tag))?,
+                        (None, None) => git_ls_remote(git, &format!("refs/tags/{}", selected.version))?,
+                    };
+                    package.git = Some(git.clone());
+                    package.commit = Some(commit);
+                }
+                (None, Some(url)) => {
+                    let checksum = match &selected.sha256 {
+                        Some(sha256) => sha256.clone(),
+                        None => fetch::sha256_of_file(&fetch::download_archive(url)?)?,
+                    };
+                    package.url = Some(url.clone());
+                    package.checksum = Some(checksum);
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("レジストリ '{}' の {} {} には git か url のどちらか一方を指定してください。", registry.name, name, selected.version),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(targets) = dependency.targets() {
+        package.targets = Some(targets.to_vec());
+    }
+
+    Ok(package)
+}
+
+// Git の取得元からバージョンとコミットを確定させる
+fn resolve_git(
+    name: &str,
+    url: &str,
+    reference: &GitReference,
+    requirement: &VersionReq,
+    requirement_str: &str,
+) -> io::Result<(String, String)> {
+    match reference {
+        GitReference::Tag(tag) => {
+            // タグがバージョンとして読める場合は要求を満たすか確認する
+            let version = match version::parse_tag(tag) {
+                Some(version) if !requirement.matches(&version) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("タグ '{}' はバージョン要求 '{}' を満たしません。", tag, requirement_str),
+                    ));
+                }
+                Some(version) => version.to_string(),
+                None => tag.to_string(),
+            };
+            Ok((version, git_ls_remote(url, &format!("refs/tags/{}", tag))?))
+        }
+        GitReference::Branch(branch) => Ok((branch.to_string(), git_ls_remote(url, &format!("refs/heads/{}", branch))?)),
+        GitReference::Rev(rev) => Ok((rev.to_string(), rev.to_string())),
+        GitReference::Requirement(_) => {
+            let tags = git_tags(url)?;
+            let names: Vec<String> = tags.iter().map(|(tag, _)| tag.clone()).collect();
+            match version::select_highest(&names, requirement) {
+                Some((tag, version)) => {
+                    let commit = tags.iter().find(|(name, _)| name == tag).map(|(_, commit)| commit.clone()).unwrap_or_default();
+                    Ok((version.to_string(), commit))
+                }
+                // バージョンとして読めるタグが無ければ "*" に限りデフォルトブランチを使う
+                None if requirement_str == "*" && names.iter().all(|tag| version::parse_tag(tag).is_none()) => {
+                    Ok(("HEAD".to_string(), git_ls_remote(url, "HEAD")?))
+                }
+                None => Err(version::unsatisfiable(name, requirement_str, &names)),
+            }
+        }
+    }
+}
+
+// リモートの参照が指すコミットを取得する
+fn git_ls_remote(url: &str, reference: &str) -> io::Result<String> {
+    let peeled = format!("{}^{{}}", reference);
+    let remote = fetch::git_remote(url)?;
+    let output = Command::new("git").args(["ls-remote", &remote, reference, &peeled]).output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "git ls-remote が失敗しました: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    // 注釈付きタグの場合は "^{}" 付きの行が実際のコミットを指す
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut commit = None;
+    for line in stdout.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(hash), Some(name)) = (fields.next(), fields.next()) {
+            if name == peeled {
+                return Ok(hash.to_string());
+            }
+            if name == reference {
+                commit = Some(hash.to_string());
+            }
+        }
+    }
+
+    commit.ok_or_else(|| Error::new(ErrorKind::NotFound, format!("'{}' が {} に見つかりません。", reference, url)))
+}
+
+// 取得元で公開されているバージョンの一覧（Git のタグまたはレジストリのインデックスから）
+// パスやアーカイブ、ブランチ指定などバージョンを列挙できない場合は None
+pub fn available_versions(name: &str, dependency: &Dependency, registries: &[Registry]) -> io::Result<Option<Vec<Version>>> {
+    let names = match dependency.source()? {
+        DependencySource::Git { url, reference: GitReference::Tag(_) | GitReference::Requirement(_) } => {
+            git_tags(url)?.into_iter().map(|(tag, _)| tag).collect()
+        }
+        DependencySource::Registry(registry_name) => registry::find(registries, name, registry_name)?.1.version_names(),
+        _ => return Ok(None),
+    };
+
+    let mut versions: Vec<Version> = names.iter().filter_map(|name: &String| version::parse_tag(name)).collect();
+    versions.sort();
+    versions.dedup();
+    Ok(Some(versions))
+}
+
+// リモートのタグ一覧と各タグが指すコミットを取得する
+fn git_tags(url: &str) -> io::Result<Vec<(String, String)>> {
+    let remote = fetch::git_remote(url)?;
+    let output = Command::new("git").args(["ls-remote", "--tags", &remote]).output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "git ls-remote が失敗しました: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut tags: Vec<(String, String)> = Vec::new();
+    for line in stdout.lines() {
+        let mut fields = line.split_whitespace();
+        let (hash, name) = match (fields.next(), fields.next()) {
+            (Some(hash), Some(name)) => (hash, name),
+            _ => continue,
+        };
+        let tag = match name.strip_prefix("refs/tags/") {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        // 注釈付きタグは "^{}" 付きの行のコミットで上書きする
+        match tag.strip_suffix("^{}") {
+            Some(tag) => match tags.iter_mut().find(|(name, _)| name == tag) {
+                Some(entry) => entry.1 = hash.to_string(),
+                None => tags.push((tag.to_string(), hash.to_string())),
+            },
+            None => {
+                if !tags.iter().any(|(name, _)| name == tag) {
+                    tags.push((tag.to_string(), hash.to_string()));
+                }
+            }
+        }
+    }
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn write_manifest(dir: &Path, content: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("cpam.toml"), content).unwrap();
+    }
+
+    fn config(content: &str) -> CpamConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    fn names(lockfile: &Lockfile) -> Vec<&str> {
+        lockfile.packages.iter().map(|package| package.name.as_str()).collect()
+    }
+
+    #[test]
+    fn resolves_transitive_path_dependencies_in_dependency_order() {
+        let dir = TempDir::new("transitive");
+        write_manifest(&dir.join("libs/b"), "");
+        write_manifest(&dir.join("libs/a"), "[dependencies]\nb = { path = \"../b\" }\n");
+        let config = config(&format!("[dependencies]\na = {{ path = \"{}\" }}\n", dir.join("libs/a").display()));
+
+        let lockfile = resolve(&config, None).unwrap();
+        assert_eq!(names(&lockfile), ["b", "a"]);
+        assert_eq!(lockfile.find("a").unwrap().dependencies, Some(vec!["b".to_string()]));
+        assert_eq!(lockfile.find("b").unwrap().path.as_deref(), Some(dir.join("libs/b").to_str().unwrap()));
+    }
+
+    #[test]
+    fn shared_dependency_is_locked_once() {
+        let dir = TempDir::new("shared");
+        write_manifest(&dir.join("c"), "");
+        write_manifest(&dir.join("a"), "[dependencies]\nc = { path = \"../c\" }\n");
+        write_manifest(&dir.join("b"), "[dependencies]\nc = { path = \"../c\" }\n");
+        let config = config(&format!(
+            "[dependencies]\na = {{ path = \"{}\" }}\nb = {{ path = \"{}\" }}\n",
+            dir.join("a").display(),
+            dir.join("b").display()
+        ));
+
+        let lockfile = resolve(&config, None).unwrap();
+        assert_eq!(names(&lockfile), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let dir = TempDir::new("cycle");
+        write_manifest(&dir.join("a"), "[dependencies]\nb = { path = \"../b\" }\n");
+        write_manifest(&dir.join("b"), "[dependencies]\na = { path = \"../a\" }\n");
+        let config = config(&format!("[dependencies]\na = {{ path = \"{}\" }}\n", dir.join("a").display()));
+
+        let error = resolve(&config, None).unwrap_err();
+        assert!(error.to_string().contains("a -> b -> a"), "{}", error);
+    }
+
+    #[test]
+    fn reports_conflicting_sources() {
+        let dir = TempDir::new("conflict");
+        write_manifest(&dir.join("c1"), "");
+        write_manifest(&dir.join("c2"), "");
+        write_manifest(&dir.join("a"), "[dependencies]\nc = { path = \"../c2\" }\n");
+        let config = config(&format!(
+            "[dependencies]\na = {{ path = \"{}\" }}\nc = {{ path = \"{}\" }}\n",
+            dir.join("a").display(),
+            dir.join("c1").display()
+        ));
+
+        let error = resolve(&config, None).unwrap_err();
+        assert!(error.to_string().contains("取得元が競合"), "{}", error);
+    }
+
+    #[test]
+    fn normalizes_redundant_components() {
+        assert_eq!(normalize_path(Path::new("libs/a/../b/./c")), PathBuf::from("libs/b/c"));
+        assert_eq!(normalize_path(Path::new("../x/../y")), PathBuf::from("../y"));
+    }
+}
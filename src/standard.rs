@@ -0,0 +1,133 @@
+use std::io::{self, Error, ErrorKind};
+
+// 言語ごとに指定できる規格（"gnu" で始まる書き方はコンパイラ拡張を有効にする）
+const C_STANDARDS: &[&str] = &["c89", "c99", "c11", "c17", "c23"];
+const CXX_STANDARDS: &[&str] = &["c++98", "c++11", "c++14", "c++17", "c++20", "c++23", "c++26"];
+const CUDA_STANDARDS: &[&str] = &["c++11", "c++14", "c++17", "c++20", "c++23"];
+
+// 生成する CMakeLists.txt が要求する CMake のバージョン（新しい規格はこれより新しい CMake が必要）
+pub const MINIMUM_CMAKE_VERSION: &str = "3.14";
+
+// cpam.toml の standard を CMake の設定に変換したもの
+pub struct LanguageStandard {
+    // CMake の C_STANDARD などに渡す番号（"11", "17" など）
+    pub version: &'static str,
+    // コンパイラ拡張（-std=gnu11 など）を使うか
+    pub extensions: bool,
+    // この規格を扱える CMake の最小バージョン
+    pub cmake_version: &'static str,
+}
+
+// 言語で指定できる規格の一覧
+pub fn supported(language: &str) -> &'static [&'static str] {
+    match language {
+        "c" => C_STANDARDS,
+        "cuda" => CUDA_STANDARDS,
+        _ => CXX_STANDARDS,
+    }
+}
+
+// standard を省略した場合の規格（C は CMake とコンパイラの既定に任せる）
+pub fn default_standard(language: &str) -> Option<&'static str> {
+    match language {
+        "c" => None,
+        _ => Some("c++17"),
+    }
+}
+
+// 規格の書式と言語との組み合わせを確認する
+pub fn parse(language: &str, standard: &str) -> io::Result<LanguageStandard> {
+    let lower = standard.to_lowercase();
+    let (name, extensions) = match lower.strip_prefix("gnu") {
+        Some(rest) if rest.starts_with("++") => (format!("c{}", rest), true),
+        Some(rest) => (format!("c{}", rest), true),
+        None => (lower.clone(), false),
+    };
+    // c90 と c18 は c89 と c17 の別名
+    let name = match name.as_str() {
+        "c90" => "c89".to_string(),
+        "c18" => "c17".to_string(),
+        _ => name,
+    };
+
+    let standards = supported(language);
+    let Some(known) = standards.iter().find(|known| **known == name) else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "規格 '{}' は言語 '{}' では使えません（{} のいずれか、拡張を使う場合は gnu11 や gnu++17 のように指定してください）。",
+                standard,
+                language,
+                standards.join(", ")
+            ),
+        ));
+    };
+
+    let version = match *known {
+        "c89" => "90",
+        known => known.trim_start_matches("c++").trim_start_matches('c'),
+    };
+    Ok(LanguageStandard { version, extensions, cmake_version: cmake_version(language, version) })
+}
+
+// C_STANDARD, CXX_STANDARD, CUDA_STANDARD がその値を受け付けるようになった CMake のバージョン
+fn cmake_version(language: &str, version: &str) -> &'static str {
+    match (language, version) {
+        ("c", "17" | "23") => "3.21",
+        (_, "26") => "3.25",
+        (_, "23") => "3.20",
+        ("cuda", "17" | "20") => "3.18",
+        _ => MINIMUM_CMAKE_VERSION,
+    }
+}
+
+// 規格の中で最も新しい CMake を要求するもの（無ければ MINIMUM_CMAKE_VERSION）
+pub fn required_cmake_version<'a>(standards: impl IntoIterator<Item = &'a LanguageStandard>) -> &'static str {
+    let key = |version: &str| -> Vec<u32> { version.split('.').filter_map(|part| part.parse().ok()).collect() };
+    standards
+        .into_iter()
+        .map(|standard| standard.cmake_version)
+        .chain([MINIMUM_CMAKE_VERSION])
+        .max_by_key(|version| key(version))
+        .unwrap_or(MINIMUM_CMAKE_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_standards_and_extensions() {
+        let standard = parse("cpp", "gnu++17").unwrap();
+        assert_eq!((standard.version, standard.extensions), ("17", true));
+        let standard = parse("c", "C11").unwrap();
+        assert_eq!((standard.version, standard.extensions), ("11", false));
+        assert_eq!(parse("c", "c90").unwrap().version, "90");
+        assert_eq!(parse("c", "gnu18").unwrap().version, "17");
+    }
+
+    #[test]
+    fn rejects_standards_of_other_languages() {
+        assert_eq!(parse("c", "c++17").err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert!(parse("cpp", "c11").is_err());
+        assert!(parse("cuda", "c++26").is_err());
+    }
+
+    #[test]
+    fn newer_standards_require_newer_cmake() {
+        assert_eq!(parse("c", "c11").unwrap().cmake_version, MINIMUM_CMAKE_VERSION);
+        assert_eq!(parse("c", "gnu23").unwrap().cmake_version, "3.21");
+        assert_eq!(parse("cpp", "c++20").unwrap().cmake_version, MINIMUM_CMAKE_VERSION);
+        assert_eq!(parse("cpp", "c++23").unwrap().cmake_version, "3.20");
+        assert_eq!(parse("cpp", "c++26").unwrap().cmake_version, "3.25");
+        assert_eq!(parse("cuda", "c++17").unwrap().cmake_version, "3.18");
+    }
+
+    #[test]
+    fn required_cmake_version_picks_the_newest() {
+        let standards = [parse("c", "c23").unwrap(), parse("cpp", "c++26").unwrap(), parse("cpp", "c++11").unwrap()];
+        assert_eq!(required_cmake_version(&standards), "3.25");
+        assert_eq!(required_cmake_version(&standards[..1]), "3.21");
+        assert_eq!(required_cmake_version(&[]), MINIMUM_CMAKE_VERSION);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::cmake::{self, KnownPackage};
+use crate::config::{CpamConfig, Dependency, DependencyKind};
+use crate::platform;
+use crate::version;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+// プロジェクトのソースディレクトリに置くマニフェストファイル名
+pub const MANIFEST_NAME: &str = "vcpkg.json";
+
+// よく使われるポートの find_package 名とリンクするターゲット
+const KNOWN_PORTS: &[KnownPackage] = &[
+    ("catch2", "Catch2", &["Catch2::Catch2WithMain"]),
+    ("curl", "CURL", &["CURL::libcurl"]),
+    ("eigen3", "Eigen3", &["Eigen3::Eigen"]),
+    ("fmt", "fmt", &["fmt::fmt"]),
+    ("glfw3", "glfw3", &["glfw"]),
+    ("gtest", "GTest", &["GTest::gtest", "GTest::gtest_main"]),
+    ("libpng", "PNG", &["PNG::PNG"]),
+    ("nlohmann-json", "nlohmann_json", &["nlohmann_json::nlohmann_json"]),
+    ("openssl", "OpenSSL", &["OpenSSL::SSL", "OpenSSL::Crypto"]),
+    ("spdlog", "spdlog", &["spdlog::spdlog"]),
+    ("sqlite3", "unofficial-sqlite3", &["unofficial::sqlite3::sqlite3"]),
+    ("zlib", "ZLIB", &["ZLIB::ZLIB"]),
+];
+
+// ポートの find_package 名とリンクするターゲット
+pub fn port_targets(port: &str, dependency: &Dependency) -> (String, Vec<String>) {
+    cmake::backend_targets(KNOWN_PORTS, port, dependency)
+}
+
+// cpam.toml の依存関係から vcpkg.json を生成・更新する（dependencies 以外の項目は残す）
+pub fn write_manifest(config: &CpamConfig, source_dir: &Path) -> io::Result<()> {
+    let manifest_path = source_dir.join(MANIFEST_NAME);
+    let existing = match fs::read_to_string(&manifest_path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let mut manifest = match &existing {
+        Some(content) => match serde_json::from_str(content) {
+            Ok(Value::Object(manifest)) => manifest,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} の解析に失敗しました。", manifest_path.display()),
+                ))
+            }
+        },
+        None => Map::new(),
+    };
+
+    if !manifest.contains_key("name") {
+        let name = config.project.as_ref().map(|project| port_name(&project.name)).unwrap_or_else(|| "project".to_string());
+        manifest.insert("name".to_string(), json!(name));
+    }
+
+    let baseline = config.vcpkg.as_ref().and_then(|vcpkg| vcpkg.baseline.clone());
+    if let Some(baseline) = &baseline {
+        manifest.insert("builtin-baseline".to_string(), json!(baseline));
+    }
+
+    let mut dependencies = Vec::new();
+    for (kind, port, dependency) in config.backend_dependencies()? {
+        let minimum = version::minimum_version(&version::parse_requirement(dependency.version())?);
+        let mut entry = Map::new();
+        entry.insert("name".to_string(), json!(port));
+        // バージョンの下限は builtin-baseline がある場合のみ指定できる
+        if let Some(minimum) = minimum.filter(|_| baseline.is_some()) {
+            entry.insert("version>=".to_string(), json!(minimum));
+        }
+        // ビルド時のツールはホスト向けにインストールする
+        if kind == DependencyKind::Build {
+            entry.insert("host".to_string(), json!(true));
+        }
+        if let Some(expression) = dependency.platforms().and_then(platform::vcpkg_expression) {
+            entry.insert("platform".to_string(), json!(expression));
+        }
+
+        if entry.len() == 1 {
+            dependencies.push(json!(port));
+        } else {
+            dependencies.push(Value::Object(entry));
+        }
+    }
+    manifest.insert("dependencies".to_string(), Value::Array(dependencies));
+
+    let content = serde_json::to_string_pretty(&Value::Object(manifest))
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{} のシリアライズに失敗: {}", MANIFEST_NAME, e)))?
+        + "\n";
+    if existing.as_deref() != Some(content.as_str()) {
+        fs::write(&manifest_path, content)?;
+        println!("{} を更新しました。", MANIFEST_NAME);
+    }
+    Ok(())
+}
+
+// vcpkg のマニフェストで使える名前（小文字の英数字とハイフン）
+fn port_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' }).collect()
+}
+
+// vcpkg のディレクトリ（[vcpkg] root または環境変数 VCPKG_ROOT）
+fn root(config: &CpamConfig) -> io::Result<PathBuf> {
+    let root = config
+        .vcpkg
+        .as_ref()
+        .and_then(|vcpkg| vcpkg.root.clone())
+        .or_else(|| env::var("VCPKG_ROOT").ok())
+        .ok_or_else(|| Error::new(
+            ErrorKind::NotFound,
+            "vcpkg が見つかりません。cpam.toml の [vcpkg] root か環境変数 VCPKG_ROOT を設定してください。",
+        ))?;
+    Ok(PathBuf::from(root))
+}
+
+// cmake の構成時に渡す引数（ツールチェーンファイルとオーバーレイ）
+pub fn configure_args(config: &CpamConfig) -> io::Result<Vec<String>> {
+    let toolchain = root(config)?.join("scripts").join("buildsystems").join("vcpkg.cmake");
+    if !toolchain.exists() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("vcpkg のツールチェーンファイル '{}' が存在しません。", toolchain.display()),
+        ));
+    }
+
+    let mut args = vec![format!("-DCMAKE_TOOLCHAIN_FILE={}", cmake_path(&toolchain)?)];
+    if let Some(overlay_ports) = config.vcpkg.as_ref().and_then(|vcpkg| vcpkg.overlay_ports.as_ref()) {
+        let overlay_ports: Vec<String> = overlay_ports.iter().map(|port| cmake_path(Path::new(port))).collect::<io::Result<_>>()?;
+        args.push(format!("-DVCPKG_OVERLAY_PORTS={}", overlay_ports.join(";")));
+    }
+    Ok(args)
+}
+
+fn cmake_path(path: &Path) -> io::Result<String> {
+    Ok(std::path::absolute(path)?.to_string_lossy().replace('\\', "/"))
+}
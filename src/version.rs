@@ -0,0 +1,126 @@
+use semver::{Comparator, Op, Version, VersionReq};
+use std::io::{self, Error, ErrorKind};
+
+// Cargo 形式のバージョン要求（"^1.2", "~1.2.3", ">=1.0, <2.0", "*" など）を解析する
+pub fn parse_requirement(requirement: &str) -> io::Result<VersionReq> {
+    VersionReq::parse(requirement.trim()).map_err(|e| Error::new(
+        ErrorKind::InvalidInput,
+        format!("バージョン要求 '{}' を解析できません: {}", requirement, e),
+    ))
+}
+
+// タグ名をバージョンとして解釈する（"v1.2.3", "1.2" などを受け付ける）
+pub fn parse_tag(tag: &str) -> Option<Version> {
+    let trimmed = tag.strip_prefix('v').or_else(|| tag.strip_prefix('V')).unwrap_or(tag);
+    if let Ok(version) = Version::parse(trimmed) {
+        return Some(version);
+    }
+
+    // "1.2" や "1" のように省略された形式は 0 で補う
+    let parts: Vec<&str> = trimmed.split('.').collect();
+    if parts.is_empty() || parts.len() > 2 || parts.iter().any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let mut numbers = parts.iter().map(|part| part.parse::<u64>().ok());
+    let major = numbers.next()??;
+    let minor = numbers.next().unwrap_or(Some(0))?;
+    Some(Version::new(major, minor, 0))
+}
+
+// 要求の下限となるバージョン（find_package などに渡す。"*" や "<2.0" のみの場合は None）
+pub fn minimum_version(requirement: &VersionReq) -> Option<String> {
+    requirement
+        .comparators
+        .iter()
+        .find(|comparator| matches!(comparator.op, Op::Exact | Op::GreaterEq | Op::Tilde | Op::Caret))
+        .map(comparator_version)
+}
+
+// 比較子のバージョン部分（省略された部分は含めない）
+pub fn comparator_version(comparator: &Comparator) -> String {
+    let mut version = comparator.major.to_string();
+    for part in [comparator.minor, comparator.patch].into_iter().map_while(|part| part) {
+        version.push_str(&format!(".{}", part));
+    }
+    if !comparator.pre.is_empty() {
+        version.push_str(&format!("-{}", comparator.pre));
+    }
+    version
+}
+
+// 要求を満たす中で最も新しいタグを選ぶ
+pub fn select_highest<'a>(tags: &'a [String], requirement: &VersionReq) -> Option<(&'a str, Version)> {
+    tags.iter()
+        .filter_map(|tag| parse_tag(tag).map(|version| (tag.as_str(), version)))
+        .filter(|(_, version)| requirement.matches(version))
+        .max_by(|(_, a), (_, b)| a.cmp(b))
+}
+
+// 要求を満たすタグが無い場合のエラー
+pub fn unsatisfiable(name: &str, requirement: &str, tags: &[String]) -> Error {
+    let mut versions: Vec<Version> = tags.iter().filter_map(|tag| parse_tag(tag)).collect();
+    versions.sort();
+    let available = if versions.is_empty() {
+        "なし".to_string()
+    } else {
+        versions.iter().map(|version| version.to_string()).collect::<Vec<_>>().join(", ")
+    };
+
+    Error::new(
+        ErrorKind::NotFound,
+        format!("'{}' のバージョン要求 '{}' を満たすバージョンがありません（利用可能: {}）。", name, requirement, available),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_cargo_style_requirements() {
+        assert!(parse_requirement("^1.2").unwrap().matches(&Version::new(1, 9, 0)));
+        assert!(!parse_requirement("~1.2.3").unwrap().matches(&Version::new(1, 3, 0)));
+        assert!(parse_requirement(" >=1.0, <2.0 ").unwrap().matches(&Version::new(1, 5, 2)));
+        assert!(parse_requirement("*").unwrap().matches(&Version::new(0, 1, 0)));
+        assert_eq!(parse_requirement("abc").unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn parses_tags_as_versions() {
+        assert_eq!(parse_tag("v1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(parse_tag("V2.0.0"), Some(Version::new(2, 0, 0)));
+        assert_eq!(parse_tag("1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(parse_tag("3"), Some(Version::new(3, 0, 0)));
+        assert_eq!(parse_tag("release-1.0"), None);
+        assert_eq!(parse_tag("1..2"), None);
+        assert_eq!(parse_tag("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn selects_highest_matching_tag() {
+        let tags = tags(&["v1.0.0", "v1.4.2", "1.10", "v2.0.0", "nightly"]);
+        let requirement = parse_requirement("^1.2").unwrap();
+        assert_eq!(select_highest(&tags, &requirement), Some(("1.10", Version::new(1, 10, 0))));
+        assert_eq!(select_highest(&tags, &parse_requirement(">=3").unwrap()), None);
+    }
+
+    #[test]
+    fn minimum_version_keeps_written_precision() {
+        assert_eq!(minimum_version(&parse_requirement("^1.2").unwrap()).as_deref(), Some("1.2"));
+        assert_eq!(minimum_version(&parse_requirement(">=1.0.3, <2").unwrap()).as_deref(), Some("1.0.3"));
+        assert_eq!(minimum_version(&parse_requirement("<2.0").unwrap()), None);
+        assert_eq!(minimum_version(&parse_requirement("*").unwrap()), None);
+    }
+
+    #[test]
+    fn unsatisfiable_lists_available_versions_in_order() {
+        let error = unsatisfiable("fmt", ">=3", &tags(&["v2.0.0", "v1.0.0", "latest"]));
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(error.to_string().contains("利用可能: 1.0.0, 2.0.0"));
+        assert!(unsatisfiable("fmt", ">=3", &[]).to_string().contains("利用可能: なし"));
+    }
+}
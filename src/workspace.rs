@@ -0,0 +1,483 @@
+use crate::config::{Backend, CpamConfig, Dependency, DependencyKind, DependencySource, WorkspaceConfig};
+use crate::resolve;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+// ワークスペースのメンバー
+pub struct Member {
+    // ワークスペースのルートからの相対パス（"/" 区切り）
+    pub dir: String,
+    pub config: CpamConfig,
+}
+
+// [workspace] のあるルートの cpam.toml と、そのメンバー
+pub struct Workspace {
+    pub root: CpamConfig,
+    // 他のメンバーに依存される側が先に並ぶ
+    pub members: Vec<Member>,
+}
+
+impl Member {
+    pub fn name(&self) -> &str {
+        self.config.project.as_ref().map(|project| project.name.as_str()).unwrap_or_default()
+    }
+}
+
+impl Workspace {
+    // カレントディレクトリをルートとしてメンバーの cpam.toml を読み込む
+    pub fn load(root: CpamConfig) -> io::Result<Self> {
+        let Some(workspace) = &root.workspace else {
+            return Err(Error::new(ErrorKind::InvalidData, "cpam.toml に [workspace] がありません。"));
+        };
+        if root.project.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "ワークスペースのルートの cpam.toml には [project] を書けません。プロジェクトはメンバーのディレクトリに置いてください。",
+            ));
+        }
+        if let Some(kind) = DependencyKind::ALL.into_iter().find(|kind| root.dependency_table(*kind).is_some()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("ワークスペースのルートの cpam.toml には [{}] を書けません。メンバーの cpam.toml に書いてください。", kind.table_name()),
+            ));
+        }
+        if root.backend()? != Backend::Cpam {
+            return Err(Error::new(ErrorKind::InvalidData, "ワークスペースでは vcpkg, Conan のバックエンドに対応していません。"));
+        }
+
+        let dirs = member_dirs(Path::new("."), workspace)?;
+        if dirs.is_empty() {
+            return Err(Error::new(ErrorKind::NotFound, "[workspace] の members に一致するメンバーがありません。"));
+        }
+
+        let mut members: Vec<Member> = Vec::new();
+        for dir in dirs {
+            let mut config = CpamConfig::load_from(&Path::new(&dir).join("cpam.toml"))?;
+            let Some(project) = &config.project else {
+                return Err(Error::new(ErrorKind::InvalidData, format!("メンバー '{}' の cpam.toml に [project] がありません。", dir)));
+            };
+            if config.workspace.is_some() {
+                return Err(Error::new(ErrorKind::InvalidData, format!("メンバー '{}' の cpam.toml に [workspace] は書けません。", dir)));
+            }
+            if config.backend()? != Backend::Cpam {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("メンバー '{}': ワークスペースでは vcpkg, Conan のバックエンドに対応していません。", dir),
+                ));
+            }
+            if let Some(other) = members.iter().find(|member| member.name() == project.name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("メンバー '{}' と '{}' のプロジェクト名 '{}' が重複しています。", other.dir, dir, project.name),
+                ));
+            }
+            if config.patch.is_some() {
+                println!("警告: メンバー '{}' の [patch] は使われません。ワークスペースのルートの cpam.toml に書いてください。", dir);
+            }
+            config.workspace_member = true;
+            members.push(Member { dir, config });
+        }
+
+        link_members(&mut members)?;
+        let members = dependency_order(members)?;
+        Ok(Workspace { root, members })
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|member| member.name() == name)
+    }
+
+    // CMake の project() に使うワークスペースの名前（ルートのディレクトリ名）
+    pub fn name(&self) -> String {
+        env::current_dir()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "workspace".to_string())
+    }
+
+    // 全メンバーの外部の依存ライブラリを1つにまとめた設定（依存関係の解決とロックファイルに使う）
+    // パス指定はルートからの相対パスにし、同じ依存ライブラリは全メンバーで同じ指定にそろっている必要がある
+    pub fn combined(&self) -> io::Result<CpamConfig> {
+        let mut dependencies: BTreeMap<String, Dependency> = BTreeMap::new();
+        // 各依存ライブラリを最初に指定したメンバー（食い違いの報告に使う）
+        let mut owners: BTreeMap<String, &str> = BTreeMap::new();
+        let mut registries = BTreeMap::new();
+
+        for member in &self.members {
+            registries.extend(member.config.registries.clone().unwrap_or_default());
+            for (_, name, dependency) in member.config.all_dependencies()? {
+                if member_dependency(&self.members, member, dependency)?.is_some() {
+                    continue;
+                }
+
+                let mut dependency = dependency.clone();
+                if let Dependency::Detailed(detail) = &mut dependency {
+                    if let Some(path) = &mut detail.path {
+                        *path = relative_to_root(&member.dir, path);
+                    }
+                }
+
+                match dependencies.get_mut(name) {
+                    Some(existing) => merge_dependency(existing, &dependency, name, owners[name], member.name())?,
+                    None => {
+                        dependencies.insert(name.to_string(), dependency);
+                        owners.insert(name.to_string(), member.name());
+                    }
+                }
+            }
+        }
+        registries.extend(self.root.registries.clone().unwrap_or_default());
+
+        Ok(CpamConfig {
+            dependencies: (!dependencies.is_empty()).then_some(dependencies),
+            patch: self.root.patch.clone(),
+            registries: (!registries.is_empty()).then_some(registries),
+            ..Default::default()
+        })
+    }
+}
+
+// [workspace] の members に一致するディレクトリ（ルートからの相対パス）
+fn member_dirs(root: &Path, workspace: &WorkspaceConfig) -> io::Result<Vec<String>> {
+    let mut dirs = Vec::new();
+    for pattern in &workspace.members {
+        let is_pattern = pattern.contains(['*', '?']);
+        for dir in expand(root, pattern)? {
+            let has_manifest = root.join(&dir).join("cpam.toml").is_file();
+            if !has_manifest && !is_pattern {
+                return Err(Error::new(ErrorKind::NotFound, format!("メンバー '{}' に cpam.toml がありません。", dir)));
+            }
+            if has_manifest && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+
+    let excluded = workspace.exclude.as_deref().unwrap_or_default();
+    dirs.retain(|dir| !excluded.iter().any(|pattern| path_matches(&clean(pattern), dir)));
+    dirs.sort();
+    Ok(dirs)
+}
+
+// "./libs/*/" のような書き方を "libs/*" にそろえる
+fn clean(pattern: &str) -> String {
+    pattern.split(['/', '\\']).filter(|component| !component.is_empty() && *component != ".").collect::<Vec<_>>().join("/")
+}
+
+// members のパターンをディレクトリに展開する（"*" と "?" は1階層の中でのみ一致する）
+fn expand(root: &Path, pattern: &str) -> io::Result<Vec<String>> {
+    let mut dirs = vec![String::new()];
+    for component in clean(pattern).split('/') {
+        let mut next = Vec::new();
+        for dir in &dirs {
+            if !component.contains(['*', '?']) {
+                next.push(join(dir, component));
+                continue;
+            }
+            let entries = match fs::read_dir(root.join(dir)) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if entry.path().is_dir() && !name.starts_with('.') && wildcard_matches(component, &name) {
+                    next.push(join(dir, &name));
+                }
+            }
+        }
+        dirs = next;
+    }
+    dirs.retain(|dir| !dir.is_empty());
+    Ok(dirs)
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let patterns: Vec<&str> = pattern.split('/').collect();
+    let components: Vec<&str> = path.split('/').collect();
+    patterns.len() == components.len() && patterns.iter().zip(&components).all(|(pattern, name)| wildcard_matches(pattern, name))
+}
+
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let mut chars = pattern.chars();
+    match chars.next() {
+        None => text.is_empty(),
+        Some('*') => text.char_indices().map(|(index, _)| index).chain([text.len()]).any(|index| wildcard_matches(chars.as_str(), &text[index..])),
+        Some('?') => {
+            let mut rest = text.chars();
+            rest.next().is_some() && wildcard_matches(chars.as_str(), rest.as_str())
+        }
+        Some(c) => text.strip_prefix(c).is_some_and(|rest| wildcard_matches(chars.as_str(), rest)),
+    }
+}
+
+// メンバーのディレクトリからの相対パスを、ルートからの相対パスにする
+fn relative_to_root(dir: &str, path: &str) -> String {
+    resolve::normalize_path(&Path::new(dir).join(path)).to_string_lossy().replace('\\', "/")
+}
+
+// パス指定が他のメンバーを指していれば、そのメンバー
+fn member_dependency<'a>(members: &'a [Member], member: &Member, dependency: &Dependency) -> io::Result<Option<&'a Member>> {
+    let DependencySource::Path(path) = dependency.source()? else {
+        return Ok(None);
+    };
+    let path = relative_to_root(&member.dir, path);
+    Ok(members.iter().find(|other| other.dir == path))
+}
+
+// 他のメンバーへの依存は、そのメンバーのターゲットをリンクする（targets の指定が無い場合）
+fn link_members(members: &mut [Member]) -> io::Result<()> {
+    let mut links = Vec::new();
+    for (index, member) in members.iter().enumerate() {
+        for (kind, name, dependency) in member.config.all_dependencies()? {
+            if let Some(other) = member_dependency(members, member, dependency)? {
+                if other.dir == member.dir {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("メンバー '{}' が自身に依存しています。", member.name())));
+                }
+                if dependency.targets().is_none() {
+                    links.push((index, kind, name.to_string(), other.name().to_string()));
+                }
+            }
+        }
+    }
+
+    for (index, kind, name, target) in links {
+        let Some(dependency) = members[index].config.dependency_table_mut(kind).as_mut().and_then(|table| table.get_mut(&name)) else {
+            continue;
+        };
+        if let Dependency::Detailed(detail) = dependency {
+            detail.targets = Some(vec![target]);
+        }
+    }
+    Ok(())
+}
+
+// 他のメンバーに依存される側が先に来るよう並べ替える（循環していればエラー）
+fn dependency_order(members: Vec<Member>) -> io::Result<Vec<Member>> {
+    let mut edges: Vec<Vec<usize>> = Vec::new();
+    for member in &members {
+        let mut children = Vec::new();
+        for (_, _, dependency) in member.config.all_dependencies()? {
+            if let Some(other) = member_dependency(&members, member, dependency)? {
+                children.extend(members.iter().position(|candidate| candidate.dir == other.dir));
+            }
+        }
+        edges.push(children);
+    }
+
+    fn visit(index: usize, edges: &[Vec<usize>], members: &[Member], path: &mut Vec<usize>, done: &mut Vec<usize>) -> io::Result<()> {
+        if let Some(start) = path.iter().position(|visited| *visited == index) {
+            let mut cycle: Vec<&str> = path[start..].iter().map(|visited| members[*visited].name()).collect();
+            cycle.push(members[index].name());
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("メンバーの依存関係が循環しています: {}", cycle.join(" -> ")),
+            ));
+        }
+        if done.contains(&index) {
+            return Ok(());
+        }
+        path.push(index);
+        for child in &edges[index] {
+            visit(*child, edges, members, path, done)?;
+        }
+        path.pop();
+        done.push(index);
+        Ok(())
+    }
+
+    let mut done = Vec::new();
+    for index in 0..members.len() {
+        visit(index, &edges, &members, &mut Vec::new(), &mut done)?;
+    }
+
+    let mut members: Vec<Option<Member>> = members.into_iter().map(Some).collect();
+    Ok(done.into_iter().filter_map(|index| members[index].take()).collect())
+}
+
+// 複数のメンバーが使う依存ライブラリの指定をまとめる
+// 取得元とバージョン要求、リンクするターゲット、cmake_options は一致している必要がある
+fn merge_dependency(existing: &mut Dependency, added: &Dependency, name: &str, owner: &str, member: &str) -> io::Result<()> {
+    let same = existing.version() == added.version()
+        && existing.source()?.id() == added.source()?.id()
+        && existing.targets() == added.targets()
+        && existing.cmake_options()? == added.cmake_options()?;
+    if !same {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "依存ライブラリ '{}' の指定がメンバー '{}' と '{}' で異なります。ワークスペースでは同じ指定にそろえてください。",
+                name, owner, member
+            ),
+        ));
+    }
+
+    // platforms は条件を合わせ、どちらかが常に使うなら常に使う
+    // optional はどちらも optional な場合のみ残す
+    let platforms = match (existing.platforms(), added.platforms()) {
+        (Some(existing), Some(added)) => {
+            let mut merged = existing.to_vec();
+            merged.extend(added.iter().filter(|platform| !existing.contains(platform)).cloned());
+            Some(merged)
+        }
+        _ => None,
+    };
+    let optional = existing.is_optional() && added.is_optional();
+    if let Dependency::Detailed(detail) = existing {
+        detail.platforms = platforms;
+        detail.optional = optional.then_some(true);
+    }
+    Ok(())
+}
+
+// カレントディレクトリがワークスペースのメンバーなら、ルートへ移動してメンバーのプロジェクト名を返す
+// 最も近い [workspace] のある cpam.toml だけを見る（それより上や、読み込めない関係の無い cpam.toml は無視する）
+pub fn enter_root() -> io::Result<Option<String>> {
+    let current = env::current_dir()?.canonicalize()?;
+    for ancestor in current.ancestors().skip(1) {
+        let manifest = ancestor.join("cpam.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        let config = match CpamConfig::load_from(&manifest) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("警告: {} を読み込めないため無視します: {}", manifest.display(), e);
+                continue;
+            }
+        };
+        let Some(workspace) = &config.workspace else {
+            continue;
+        };
+
+        for dir in member_dirs(ancestor, workspace)? {
+            if ancestor.join(&dir).canonicalize().ok().as_deref() != Some(current.as_path()) {
+                continue;
+            }
+            let member = CpamConfig::load_from(&current.join("cpam.toml"))?;
+            let name = member.project.map(|project| project.name).unwrap_or(dir);
+            env::set_current_dir(ancestor)?;
+            println!("ワークスペース {} のメンバー '{}' として実行します。", ancestor.display(), name);
+            return Ok(Some(name));
+        }
+        return Ok(None);
+    }
+    Ok(None)
+}
+
+// 依存関係の解決に使う設定（ワークスペースでは全メンバーをまとめたもの）
+// メンバーのディレクトリで実行した場合はワークスペースのルートへ移動する
+pub fn load_dependency_config() -> io::Result<CpamConfig> {
+    enter_root()?;
+    let config = CpamConfig::load()?;
+    if config.workspace.is_none() {
+        return Ok(config);
+    }
+    Workspace::load(config)?.combined()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn workspace(members: &[&str], exclude: &[&str]) -> WorkspaceConfig {
+        WorkspaceConfig {
+            members: members.iter().map(|member| member.to_string()).collect(),
+            exclude: (!exclude.is_empty()).then(|| exclude.iter().map(|pattern| pattern.to_string()).collect()),
+        }
+    }
+
+    fn member(dir: &str, manifest: &str) -> Member {
+        let mut config: CpamConfig = toml::from_str(manifest).unwrap();
+        config.workspace_member = true;
+        Member { dir: dir.to_string(), config }
+    }
+
+    fn project(name: &str, dependencies: &str) -> String {
+        format!(
+            "[project]\nname = \"{}\"\nlanguage = \"cpp\"\nbuild_tool = \"make\"\nproject_type = \"lib\"\n\n[dependencies]\n{}",
+            name, dependencies
+        )
+    }
+
+    #[test]
+    fn wildcards_match_within_a_component() {
+        assert!(wildcard_matches("*", "core"));
+        assert!(wildcard_matches("lib-*", "lib-core"));
+        assert!(wildcard_matches("lib?", "libs"));
+        assert!(wildcard_matches("*-*", "a-b-c"));
+        assert!(!wildcard_matches("lib?", "lib"));
+        assert!(!wildcard_matches("lib-*", "app-core"));
+        assert!(path_matches("libs/*", "libs/core"));
+        assert!(!path_matches("libs/*", "libs/core/nested"));
+    }
+
+    #[test]
+    fn expands_member_globs_with_manifests_only() {
+        let root = TempDir::new("globs");
+        for dir in ["libs/core", "libs/util", "libs/unused", "apps/app", "libs/.hidden"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("cpam.toml"), "").unwrap();
+        }
+        fs::create_dir_all(root.join("libs/docs")).unwrap();
+
+        let dirs = member_dirs(&root, &workspace(&["./libs/*/", "apps/app", "libs/core"], &["libs/un*"])).unwrap();
+        assert_eq!(dirs, ["apps/app", "libs/core", "libs/util"]);
+    }
+
+    #[test]
+    fn explicit_member_without_manifest_is_an_error() {
+        let root = TempDir::new("missing");
+        fs::create_dir_all(root.join("libs/docs")).unwrap();
+        assert_eq!(member_dirs(&root, &workspace(&["libs/docs"], &[])).unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(member_dirs(&root, &workspace(&["libs/*", "nothing/*"], &[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn orders_members_after_their_dependencies() {
+        let members = vec![
+            member("apps/app", &project("app", "core = { path = \"../../libs/core\" }\nutil = { path = \"../../libs/util\" }\n")),
+            member("libs/util", &project("util", "core = { path = \"../core\" }\n")),
+            member("libs/core", &project("core", "")),
+        ];
+        let ordered = dependency_order(members).unwrap();
+        let names: Vec<&str> = ordered.iter().map(|member| member.name()).collect();
+        assert_eq!(names, ["core", "util", "app"]);
+    }
+
+    #[test]
+    fn reports_member_cycles() {
+        let members = vec![
+            member("a", &project("a", "b = { path = \"../b\" }\n")),
+            member("b", &project("b", "a = { path = \"../a\" }\n")),
+        ];
+        let error = dependency_order(members).err().unwrap();
+        assert!(error.to_string().contains("a -> b -> a"), "{}", error);
+    }
+
+    #[test]
+    fn links_member_targets_unless_given() {
+        let mut members = vec![
+            member("app", &project("app", "core = { path = \"../core\" }\nutil = { path = \"../util\", targets = [\"util::all\"] }\n")),
+            member("core", &project("core-lib", "")),
+            member("util", &project("util", "")),
+        ];
+        link_members(&mut members).unwrap();
+        let dependencies = members[0].config.dependencies.as_ref().unwrap();
+        assert_eq!(dependencies["core"].targets(), Some(&["core-lib".to_string()][..]));
+        assert_eq!(dependencies["util"].targets(), Some(&["util::all".to_string()][..]));
+    }
+}